@@ -0,0 +1,190 @@
+use crate::error::MidgardError;
+use crate::models::{
+    ActionsPage, DepthHistoryInterval, EarningsHistoryInterval, History, Interval,
+    LiquidityChangesInterval, NetworkInfo, PoolDetail, RunepoolHistoryInterval,
+    SaversHistoryInterval, SwapHistoryInterval, TvlHistoryInterval,
+};
+use crate::transport::{HttpTransport, Transport};
+use percent_encoding::{utf8_percent_encode, AsciiSet, PercentEncode, NON_ALPHANUMERIC};
+
+// Left as they are in a path segment or query value: the unreserved characters of RFC 3986.
+const UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Escapes a pool name or page token for use in a request path or query string.
+fn encode(value: &str) -> PercentEncode<'_> {
+    utf8_percent_encode(value, UNRESERVED)
+}
+
+#[derive(Clone)]
+pub struct MidgardClient<T: Transport = HttpTransport> {
+    transport: T,
+}
+
+impl MidgardClient<HttpTransport> {
+    pub fn new() -> Self {
+        Self::with_transport(HttpTransport::default())
+    }
+
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self::with_transport(HttpTransport::new(base_url))
+    }
+
+    /// Configured from the environment, see [`HttpTransport::from_env`].
+    pub fn from_env() -> Self {
+        Self::with_transport(HttpTransport::from_env())
+    }
+}
+
+impl Default for MidgardClient<HttpTransport> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Transport> MidgardClient<T> {
+    pub fn with_transport(transport: T) -> Self {
+        MidgardClient { transport }
+    }
+
+    pub async fn depth_history(
+        &self,
+        pool: &str,
+        from: i64,
+        count: u32,
+    ) -> Result<History<DepthHistoryInterval>, MidgardError> {
+        let path = format!(
+            "/v2/history/depths/{}?interval=hour&from={}&count={}",
+            encode(pool),
+            from,
+            count
+        );
+        self.history(&path).await
+    }
+
+    pub async fn savers_history(
+        &self,
+        pool: &str,
+        from: i64,
+        count: u32,
+    ) -> Result<History<SaversHistoryInterval>, MidgardError> {
+        let path = format!(
+            "/v2/history/savers/{}?interval=hour&from={}&count={}",
+            encode(pool),
+            from,
+            count
+        );
+        self.history(&path).await
+    }
+
+    pub async fn swaps_history(
+        &self,
+        from: i64,
+        count: u32,
+    ) -> Result<History<SwapHistoryInterval>, MidgardError> {
+        let path = format!(
+            "/v2/history/swaps?interval=hour&from={}&count={}",
+            from, count
+        );
+        self.history(&path).await
+    }
+
+    pub async fn earnings_history(
+        &self,
+        from: i64,
+        count: u32,
+    ) -> Result<History<EarningsHistoryInterval>, MidgardError> {
+        let path = format!(
+            "/v2/history/earnings?interval=hour&from={}&count={}",
+            from, count
+        );
+        self.history(&path).await
+    }
+
+    pub async fn runepool_history(
+        &self,
+        from: i64,
+        count: u32,
+    ) -> Result<History<RunepoolHistoryInterval>, MidgardError> {
+        let path = format!(
+            "/v2/history/runepool?interval=hour&from={}&count={}",
+            from, count
+        );
+        self.history(&path).await
+    }
+
+    /// Liquidity added to and withdrawn from `pool`, or from every pool when it is `None`.
+    pub async fn liquidity_changes_history(
+        &self,
+        pool: Option<&str>,
+        from: i64,
+        count: u32,
+    ) -> Result<History<LiquidityChangesInterval>, MidgardError> {
+        let mut path = format!(
+            "/v2/history/liquidity_changes?interval=hour&from={}&count={}",
+            from, count
+        );
+        if let Some(pool) = pool {
+            path.push_str(&format!("&pool={}", encode(pool)));
+        }
+        self.history(&path).await
+    }
+
+    pub async fn tvl_history(
+        &self,
+        from: i64,
+        count: u32,
+    ) -> Result<History<TvlHistoryInterval>, MidgardError> {
+        let path = format!(
+            "/v2/history/tvl?interval=hour&from={}&count={}",
+            from, count
+        );
+        self.history(&path).await
+    }
+
+    async fn history<I: Interval>(&self, path: &str) -> Result<History<I>, MidgardError> {
+        let raw = self.transport.get_json(path).await?;
+        History::from_value(self.transport.url(path), raw)
+    }
+
+    /// Actions from `from` (inclusive) to before `to`, in seconds, newest first. Following
+    /// pages are requested with the previous page's `next_page_token`.
+    pub async fn actions(
+        &self,
+        from: Option<i64>,
+        to: Option<i64>,
+        next_page_token: Option<&str>,
+        limit: u32,
+    ) -> Result<ActionsPage, MidgardError> {
+        let mut path = format!("/v2/actions?limit={}", limit);
+        if let Some(from) = from {
+            path.push_str(&format!("&fromTimestamp={}", from));
+        }
+        if let Some(to) = to {
+            path.push_str(&format!("&timestamp={}", to));
+        }
+        if let Some(token) = next_page_token {
+            path.push_str(&format!("&nextPageToken={}", encode(token)));
+        }
+        let raw = self.transport.get_json(&path).await?;
+        ActionsPage::from_value(self.transport.url(&path), raw)
+    }
+
+    pub async fn network(&self) -> Result<NetworkInfo, MidgardError> {
+        let response = self.transport.get_json("/v2/network").await?;
+        NetworkInfo::from_value(&response)
+    }
+
+    pub async fn pools(&self) -> Result<Vec<PoolDetail>, MidgardError> {
+        let response = self.transport.get_json("/v2/pools").await?;
+        response
+            .as_array()
+            .ok_or_else(|| MidgardError::MissingField("pools".to_string()))?
+            .iter()
+            .map(PoolDetail::from_value)
+            .collect()
+    }
+}
@@ -0,0 +1,60 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum MidgardError {
+    /// The request could not be sent or Midgard answered with an error status.
+    Http(reqwest::Error),
+    /// The response body is not valid JSON.
+    Decode(serde_json::Error),
+    /// A field the typed response needs is absent or null.
+    MissingField(String),
+    /// A numeric field could not be parsed (Midgard sends most numbers as strings).
+    ParseNumber { field: String, value: String },
+    /// The response decoded but its values are inconsistent.
+    Invalid(String),
+    /// A recorded page could not be read by the fixture transport.
+    Fixture {
+        path: String,
+        source: std::io::Error,
+    },
+}
+
+impl fmt::Display for MidgardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MidgardError::Http(err) => write!(f, "Midgard request failed: {}", err),
+            MidgardError::Decode(err) => write!(f, "Invalid JSON from Midgard: {}", err),
+            MidgardError::MissingField(field) => write!(f, "Missing field `{}`", field),
+            MidgardError::ParseNumber { field, value } => {
+                write!(f, "Field `{}` is not a valid number: {:?}", field, value)
+            }
+            MidgardError::Invalid(reason) => write!(f, "Invalid data: {}", reason),
+            MidgardError::Fixture { path, source } => {
+                write!(f, "Could not read fixture {}: {}", path, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MidgardError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MidgardError::Http(err) => Some(err),
+            MidgardError::Decode(err) => Some(err),
+            MidgardError::Fixture { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for MidgardError {
+    fn from(err: reqwest::Error) -> Self {
+        MidgardError::Http(err)
+    }
+}
+
+impl From<serde_json::Error> for MidgardError {
+    fn from(err: serde_json::Error) -> Self {
+        MidgardError::Decode(err)
+    }
+}
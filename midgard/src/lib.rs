@@ -0,0 +1,10 @@
+pub mod client;
+pub mod error;
+pub mod models;
+pub mod retry;
+pub mod transport;
+pub use client::MidgardClient;
+pub use error::MidgardError;
+pub use models::*;
+pub use retry::{RateLimiter, RetryPolicy};
+pub use transport::{FixtureTransport, HttpTransport, RequestEvent, Transport};
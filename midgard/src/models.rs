@@ -0,0 +1,877 @@
+use crate::error::MidgardError;
+use serde_json::Value;
+use std::str::FromStr;
+
+/// A single interval of one of Midgard's `/v2/history/*` endpoints.
+pub trait Interval: Sized {
+    fn from_value(value: &Value) -> Result<Self, MidgardError>;
+    fn start_time(&self) -> i64;
+    fn end_time(&self) -> i64;
+}
+
+/// The `intervals` of a `/v2/history/*` response, decoded into `T`, along with the URL
+/// and JSON body they came from so the page can be archived as fetched. Intervals that
+/// fail to decode or validate are set aside in `rejected` instead of failing the page.
+#[derive(Debug)]
+pub struct History<T> {
+    pub url: String,
+    pub raw: Value,
+    pub intervals: Vec<T>,
+    pub rejected: Vec<RejectedInterval>,
+}
+
+/// An interval of a page that could not be decoded or is inconsistent.
+#[derive(Debug)]
+pub struct RejectedInterval {
+    pub raw: Value,
+    pub start_time: Option<i64>, // Read best-effort, absent when unparseable
+    pub end_time: Option<i64>,
+    pub error: MidgardError,
+}
+
+impl<T: Interval> History<T> {
+    pub fn from_value(url: impl Into<String>, raw: Value) -> Result<Self, MidgardError> {
+        let mut intervals = Vec::new();
+        let mut rejected = Vec::new();
+        for value in raw["intervals"]
+            .as_array()
+            .ok_or_else(|| MidgardError::MissingField("intervals".to_string()))?
+        {
+            match T::from_value(value).and_then(validate) {
+                Ok(interval) => intervals.push(interval),
+                Err(error) => rejected.push(RejectedInterval {
+                    raw: value.clone(),
+                    start_time: parse_number(value, "startTime").ok(),
+                    end_time: parse_number(value, "endTime").ok(),
+                    error,
+                }),
+            }
+        }
+        Ok(History {
+            url: url.into(),
+            raw,
+            intervals,
+            rejected,
+        })
+    }
+
+    /// Drops the intervals starting at or after `to`.
+    pub fn retain_before(&mut self, to: i64) {
+        self.intervals.retain(|i| i.start_time() < to);
+        self.rejected
+            .retain(|r| r.start_time.is_none_or(|start_time| start_time < to));
+    }
+
+    /// `endTime` of the last interval of the page, counting rejected ones, so paging
+    /// moves past them.
+    pub fn last_end_time(&self) -> Option<i64> {
+        let decoded = self.intervals.iter().map(|i| i.end_time());
+        let rejected = self.rejected.iter().filter_map(|r| r.end_time);
+        decoded.chain(rejected).max()
+    }
+}
+
+fn validate<T: Interval>(interval: T) -> Result<T, MidgardError> {
+    if interval.end_time() <= interval.start_time() {
+        return Err(MidgardError::Invalid(format!(
+            "endTime {} is not after startTime {}",
+            interval.end_time(),
+            interval.start_time()
+        )));
+    }
+    Ok(interval)
+}
+
+fn field<'a>(value: &'a Value, name: &str) -> Result<&'a Value, MidgardError> {
+    match value.get(name) {
+        Some(Value::Null) | None => Err(MidgardError::MissingField(name.to_string())),
+        Some(field) => Ok(field),
+    }
+}
+
+pub(crate) fn parse_string(value: &Value, name: &str) -> Result<String, MidgardError> {
+    match field(value, name)? {
+        Value::String(s) => Ok(s.clone()),
+        other => Ok(other.to_string()),
+    }
+}
+
+// Midgard encodes numbers as strings; plain JSON numbers are accepted as well.
+pub(crate) fn parse_number<T: FromStr>(value: &Value, name: &str) -> Result<T, MidgardError> {
+    let raw = match field(value, name)? {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        other => other.to_string(),
+    };
+    raw.parse::<T>().map_err(|_| MidgardError::ParseNumber {
+        field: name.to_string(),
+        value: raw,
+    })
+}
+
+// "NaN" and "inf" parse as f64 but cannot be stored as DECIMAL, so they are rejected here.
+pub(crate) fn parse_f64(value: &Value, name: &str) -> Result<f64, MidgardError> {
+    let number: f64 = parse_number(value, name)?;
+    if number.is_finite() {
+        Ok(number)
+    } else {
+        Err(MidgardError::ParseNumber {
+            field: name.to_string(),
+            value: number.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DepthHistoryInterval {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub asset_depth: i64,
+    pub asset_price: f64,
+    pub asset_price_usd: f64,
+    pub liquidity_units: i64,
+    pub luvi: f64,
+    pub members_count: i32,
+    pub rune_depth: i64,
+    pub synth_supply: i64,
+    pub synth_units: i64,
+    pub units: i64,
+}
+
+impl Interval for DepthHistoryInterval {
+    fn from_value(value: &Value) -> Result<Self, MidgardError> {
+        Ok(DepthHistoryInterval {
+            start_time: parse_number(value, "startTime")?,
+            end_time: parse_number(value, "endTime")?,
+            asset_depth: parse_number(value, "assetDepth")?,
+            asset_price: parse_f64(value, "assetPrice")?,
+            asset_price_usd: parse_f64(value, "assetPriceUSD")?,
+            liquidity_units: parse_number(value, "liquidityUnits")?,
+            luvi: parse_f64(value, "luvi")?,
+            members_count: parse_number(value, "membersCount")?,
+            rune_depth: parse_number(value, "runeDepth")?,
+            synth_supply: parse_number(value, "synthSupply")?,
+            synth_units: parse_number(value, "synthUnits")?,
+            units: parse_number(value, "units")?,
+        })
+    }
+
+    fn start_time(&self) -> i64 {
+        self.start_time
+    }
+
+    fn end_time(&self) -> i64 {
+        self.end_time
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SwapHistoryInterval {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub to_asset_count: i64,
+    pub to_rune_count: i64,
+    pub to_trade_count: i64,
+    pub from_trade_count: i64,
+    pub synth_mint_count: i64,
+    pub synth_redeem_count: i64,
+    pub total_count: i64,
+    pub to_asset_volume: i64,
+    pub to_rune_volume: i64,
+    pub to_trade_volume: i64,
+    pub from_trade_volume: i64,
+    pub synth_mint_volume: i64,
+    pub synth_redeem_volume: i64,
+    pub total_volume: i64,
+    pub to_asset_volume_usd: f64,
+    pub to_rune_volume_usd: f64,
+    pub to_trade_volume_usd: f64,
+    pub from_trade_volume_usd: f64,
+    pub synth_mint_volume_usd: f64,
+    pub synth_redeem_volume_usd: f64,
+    pub total_volume_usd: f64,
+    pub to_asset_fees: i64,
+    pub to_rune_fees: i64,
+    pub to_trade_fees: i64,
+    pub from_trade_fees: i64,
+    pub synth_mint_fees: i64,
+    pub synth_redeem_fees: i64,
+    pub total_fees: i64,
+    pub to_asset_average_slip: f64,
+    pub to_rune_average_slip: f64,
+    pub to_trade_average_slip: f64,
+    pub from_trade_average_slip: f64,
+    pub synth_mint_average_slip: f64,
+    pub synth_redeem_average_slip: f64,
+    pub average_slip: f64,
+    pub rune_price_usd: f64,
+}
+
+impl Interval for SwapHistoryInterval {
+    fn from_value(value: &Value) -> Result<Self, MidgardError> {
+        Ok(SwapHistoryInterval {
+            start_time: parse_number(value, "startTime")?,
+            end_time: parse_number(value, "endTime")?,
+            to_asset_count: parse_number(value, "toAssetCount")?,
+            to_rune_count: parse_number(value, "toRuneCount")?,
+            to_trade_count: parse_number(value, "toTradeCount")?,
+            from_trade_count: parse_number(value, "fromTradeCount")?,
+            synth_mint_count: parse_number(value, "synthMintCount")?,
+            synth_redeem_count: parse_number(value, "synthRedeemCount")?,
+            total_count: parse_number(value, "totalCount")?,
+            to_asset_volume: parse_number(value, "toAssetVolume")?,
+            to_rune_volume: parse_number(value, "toRuneVolume")?,
+            to_trade_volume: parse_number(value, "toTradeVolume")?,
+            from_trade_volume: parse_number(value, "fromTradeVolume")?,
+            synth_mint_volume: parse_number(value, "synthMintVolume")?,
+            synth_redeem_volume: parse_number(value, "synthRedeemVolume")?,
+            total_volume: parse_number(value, "totalVolume")?,
+            to_asset_volume_usd: parse_f64(value, "toAssetVolumeUSD")?,
+            to_rune_volume_usd: parse_f64(value, "toRuneVolumeUSD")?,
+            to_trade_volume_usd: parse_f64(value, "toTradeVolumeUSD")?,
+            from_trade_volume_usd: parse_f64(value, "fromTradeVolumeUSD")?,
+            synth_mint_volume_usd: parse_f64(value, "synthMintVolumeUSD")?,
+            synth_redeem_volume_usd: parse_f64(value, "synthRedeemVolumeUSD")?,
+            total_volume_usd: parse_f64(value, "totalVolumeUSD")?,
+            to_asset_fees: parse_number(value, "toAssetFees")?,
+            to_rune_fees: parse_number(value, "toRuneFees")?,
+            to_trade_fees: parse_number(value, "toTradeFees")?,
+            from_trade_fees: parse_number(value, "fromTradeFees")?,
+            synth_mint_fees: parse_number(value, "synthMintFees")?,
+            synth_redeem_fees: parse_number(value, "synthRedeemFees")?,
+            total_fees: parse_number(value, "totalFees")?,
+            to_asset_average_slip: parse_f64(value, "toAssetAverageSlip")?,
+            to_rune_average_slip: parse_f64(value, "toRuneAverageSlip")?,
+            to_trade_average_slip: parse_f64(value, "toTradeAverageSlip")?,
+            from_trade_average_slip: parse_f64(value, "fromTradeAverageSlip")?,
+            synth_mint_average_slip: parse_f64(value, "synthMintAverageSlip")?,
+            synth_redeem_average_slip: parse_f64(value, "synthRedeemAverageSlip")?,
+            average_slip: parse_f64(value, "averageSlip")?,
+            rune_price_usd: parse_f64(value, "runePriceUSD")?,
+        })
+    }
+
+    fn start_time(&self) -> i64 {
+        self.start_time
+    }
+
+    fn end_time(&self) -> i64 {
+        self.end_time
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EarningsHistoryPool {
+    pub pool: String,
+    pub asset_liquidity_fees: i64,
+    pub earnings: i64,
+    pub rewards: i64,
+    pub rune_liquidity_fees: i64,
+    pub saver_earning: i64,
+    pub total_liquidity_fees_rune: i64,
+}
+
+impl EarningsHistoryPool {
+    fn from_value(value: &Value) -> Result<Self, MidgardError> {
+        Ok(EarningsHistoryPool {
+            pool: parse_string(value, "pool")?,
+            asset_liquidity_fees: parse_number(value, "assetLiquidityFees")?,
+            earnings: parse_number(value, "earnings")?,
+            rewards: parse_number(value, "rewards")?,
+            rune_liquidity_fees: parse_number(value, "runeLiquidityFees")?,
+            saver_earning: parse_number(value, "saverEarning")?,
+            total_liquidity_fees_rune: parse_number(value, "totalLiquidityFeesRune")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EarningsHistoryInterval {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub avg_node_count: f64,
+    pub block_rewards: i64,
+    pub bonding_earnings: i64,
+    pub earnings: i64,
+    pub liquidity_earnings: i64,
+    pub liquidity_fees: i64,
+    pub rune_price_usd: f64,
+    pub pools: Vec<EarningsHistoryPool>,
+}
+
+impl Interval for EarningsHistoryInterval {
+    fn from_value(value: &Value) -> Result<Self, MidgardError> {
+        let pools = field(value, "pools")?
+            .as_array()
+            .ok_or_else(|| MidgardError::MissingField("pools".to_string()))?
+            .iter()
+            .map(EarningsHistoryPool::from_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(EarningsHistoryInterval {
+            start_time: parse_number(value, "startTime")?,
+            end_time: parse_number(value, "endTime")?,
+            avg_node_count: parse_f64(value, "avgNodeCount")?,
+            block_rewards: parse_number(value, "blockRewards")?,
+            bonding_earnings: parse_number(value, "bondingEarnings")?,
+            earnings: parse_number(value, "earnings")?,
+            liquidity_earnings: parse_number(value, "liquidityEarnings")?,
+            liquidity_fees: parse_number(value, "liquidityFees")?,
+            rune_price_usd: parse_f64(value, "runePriceUSD")?,
+            pools,
+        })
+    }
+
+    fn start_time(&self) -> i64 {
+        self.start_time
+    }
+
+    fn end_time(&self) -> i64 {
+        self.end_time
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RunepoolHistoryInterval {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub units: i64,
+    pub count: i32,
+}
+
+impl Interval for RunepoolHistoryInterval {
+    fn from_value(value: &Value) -> Result<Self, MidgardError> {
+        Ok(RunepoolHistoryInterval {
+            start_time: parse_number(value, "startTime")?,
+            end_time: parse_number(value, "endTime")?,
+            units: parse_number(value, "units")?,
+            count: parse_number(value, "count")?,
+        })
+    }
+
+    fn start_time(&self) -> i64 {
+        self.start_time
+    }
+
+    fn end_time(&self) -> i64 {
+        self.end_time
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SaversHistoryInterval {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub savers_count: i64,
+    pub savers_depth: i64,
+    pub savers_units: i64,
+}
+
+impl Interval for SaversHistoryInterval {
+    fn from_value(value: &Value) -> Result<Self, MidgardError> {
+        Ok(SaversHistoryInterval {
+            start_time: parse_number(value, "startTime")?,
+            end_time: parse_number(value, "endTime")?,
+            savers_count: parse_number(value, "saversCount")?,
+            savers_depth: parse_number(value, "saversDepth")?,
+            savers_units: parse_number(value, "saversUnits")?,
+        })
+    }
+
+    fn start_time(&self) -> i64 {
+        self.start_time
+    }
+
+    fn end_time(&self) -> i64 {
+        self.end_time
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LiquidityChangesInterval {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub add_asset_liquidity_volume: i64,
+    pub add_rune_liquidity_volume: i64,
+    pub add_liquidity_volume: i64,
+    pub add_liquidity_volume_usd: f64,
+    pub add_liquidity_count: i64,
+    pub remove_asset_liquidity_volume: i64,
+    pub remove_rune_liquidity_volume: i64,
+    pub remove_liquidity_volume: i64,
+    pub remove_liquidity_volume_usd: f64,
+    pub remove_liquidity_count: i64,
+    pub net_change: i64,
+    pub net_change_usd: f64,
+    pub rune_price_usd: f64,
+}
+
+impl Interval for LiquidityChangesInterval {
+    fn from_value(value: &Value) -> Result<Self, MidgardError> {
+        Ok(LiquidityChangesInterval {
+            start_time: parse_number(value, "startTime")?,
+            end_time: parse_number(value, "endTime")?,
+            add_asset_liquidity_volume: parse_number(value, "addAssetLiquidityVolume")?,
+            add_rune_liquidity_volume: parse_number(value, "addRuneLiquidityVolume")?,
+            add_liquidity_volume: parse_number(value, "addLiquidityVolume")?,
+            add_liquidity_volume_usd: parse_f64(value, "addLiquidityVolumeUSD")?,
+            add_liquidity_count: parse_number(value, "addLiquidityCount")?,
+            remove_asset_liquidity_volume: parse_number(value, "removeAssetLiquidityVolume")?,
+            remove_rune_liquidity_volume: parse_number(value, "removeRuneLiquidityVolume")?,
+            remove_liquidity_volume: parse_number(value, "removeLiquidityVolume")?,
+            remove_liquidity_volume_usd: parse_f64(value, "removeLiquidityVolumeUSD")?,
+            remove_liquidity_count: parse_number(value, "removeLiquidityCount")?,
+            net_change: parse_number(value, "netChange")?,
+            net_change_usd: parse_f64(value, "netChangeUSD")?,
+            rune_price_usd: parse_f64(value, "runePriceUSD")?,
+        })
+    }
+
+    fn start_time(&self) -> i64 {
+        self.start_time
+    }
+
+    fn end_time(&self) -> i64 {
+        self.end_time
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TvlHistoryPoolDepth {
+    pub pool: String,
+    pub total_depth: i64,
+}
+
+impl TvlHistoryPoolDepth {
+    fn from_value(value: &Value) -> Result<Self, MidgardError> {
+        Ok(TvlHistoryPoolDepth {
+            pool: parse_string(value, "pool")?,
+            total_depth: parse_number(value, "totalDepth")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TvlHistoryInterval {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub total_value_pooled: i64,
+    pub total_value_bonded: i64,
+    pub total_value_locked: i64,
+    pub rune_price_usd: f64,
+    pub pools_depth: Vec<TvlHistoryPoolDepth>,
+}
+
+impl Interval for TvlHistoryInterval {
+    fn from_value(value: &Value) -> Result<Self, MidgardError> {
+        let pools_depth = field(value, "poolsDepth")?
+            .as_array()
+            .ok_or_else(|| MidgardError::MissingField("poolsDepth".to_string()))?
+            .iter()
+            .map(TvlHistoryPoolDepth::from_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(TvlHistoryInterval {
+            start_time: parse_number(value, "startTime")?,
+            end_time: parse_number(value, "endTime")?,
+            total_value_pooled: parse_number(value, "totalValuePooled")?,
+            total_value_bonded: parse_number(value, "totalValueBonded")?,
+            total_value_locked: parse_number(value, "totalValueLocked")?,
+            rune_price_usd: parse_f64(value, "runePriceUSD")?,
+            pools_depth,
+        })
+    }
+
+    fn start_time(&self) -> i64 {
+        self.start_time
+    }
+
+    fn end_time(&self) -> i64 {
+        self.end_time
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ActionCoin {
+    pub asset: String,
+    pub amount: i64,
+}
+
+/// One side (`in` or `out`) of an action: an address and the coins it sent or received.
+#[derive(Debug, Clone)]
+pub struct ActionTransfer {
+    pub address: String,
+    pub tx_id: String,
+    pub coins: Vec<ActionCoin>,
+}
+
+impl ActionTransfer {
+    fn from_value(value: &Value) -> Result<Self, MidgardError> {
+        let coins = field(value, "coins")?
+            .as_array()
+            .ok_or_else(|| MidgardError::MissingField("coins".to_string()))?
+            .iter()
+            .map(|coin| {
+                Ok(ActionCoin {
+                    asset: parse_string(coin, "asset")?,
+                    amount: parse_number(coin, "amount")?,
+                })
+            })
+            .collect::<Result<Vec<_>, MidgardError>>()?;
+        Ok(ActionTransfer {
+            // Empty for some outbounds, e.g. refunds not yet sent
+            address: parse_string(value, "address").unwrap_or_default(),
+            tx_id: parse_string(value, "txID").unwrap_or_default(),
+            coins,
+        })
+    }
+}
+
+/// A single swap, add, withdraw, etc. from `/v2/actions`.
+#[derive(Debug, Clone)]
+pub struct Action {
+    pub date: i64, // UNIX timestamp in nanoseconds
+    pub height: i64,
+    pub action_type: String,
+    pub status: String, // success or pending
+    pub pools: Vec<String>,
+    pub inbound: Vec<ActionTransfer>,
+    pub outbound: Vec<ActionTransfer>,
+    pub metadata: Value, // Type-specific details, kept as returned
+}
+
+impl Action {
+    pub fn from_value(value: &Value) -> Result<Self, MidgardError> {
+        let transfers = |name: &str| {
+            field(value, name)?
+                .as_array()
+                .ok_or_else(|| MidgardError::MissingField(name.to_string()))?
+                .iter()
+                .map(ActionTransfer::from_value)
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let pools = field(value, "pools")?
+            .as_array()
+            .ok_or_else(|| MidgardError::MissingField("pools".to_string()))?
+            .iter()
+            .map(|pool| pool.as_str().unwrap_or_default().to_string())
+            .collect();
+        Ok(Action {
+            date: parse_number(value, "date")?,
+            height: parse_number(value, "height")?,
+            action_type: parse_string(value, "type")?,
+            status: parse_string(value, "status")?,
+            pools,
+            inbound: transfers("in")?,
+            outbound: transfers("out")?,
+            metadata: value.get("metadata").cloned().unwrap_or(Value::Null),
+        })
+    }
+
+    /// `date` in seconds.
+    pub fn timestamp(&self) -> i64 {
+        self.date.div_euclid(1_000_000_000)
+    }
+
+    /// The id of the first inbound transaction, empty when there is none.
+    pub fn tx_id(&self) -> &str {
+        self.inbound
+            .iter()
+            .map(|transfer| transfer.tx_id.as_str())
+            .find(|tx_id| !tx_id.is_empty())
+            .unwrap_or_default()
+    }
+}
+
+/// A page of `/v2/actions`, newest first, with the token of the next (older) page. Actions
+/// that fail to decode are set aside in `rejected`, keyed by their `date` in seconds.
+#[derive(Debug)]
+pub struct ActionsPage {
+    pub url: String,
+    pub raw: Value,
+    pub actions: Vec<Action>,
+    pub rejected: Vec<RejectedInterval>,
+    pub next_page_token: Option<String>,
+}
+
+impl ActionsPage {
+    pub fn from_value(url: impl Into<String>, raw: Value) -> Result<Self, MidgardError> {
+        let mut actions = Vec::new();
+        let mut rejected = Vec::new();
+        for value in raw["actions"]
+            .as_array()
+            .ok_or_else(|| MidgardError::MissingField("actions".to_string()))?
+        {
+            match Action::from_value(value) {
+                Ok(action) => actions.push(action),
+                Err(error) => {
+                    let start_time = parse_number::<i64>(value, "date")
+                        .ok()
+                        .map(|date| date.div_euclid(1_000_000_000));
+                    rejected.push(RejectedInterval {
+                        raw: value.clone(),
+                        start_time,
+                        end_time: start_time.map(|start_time| start_time + 1),
+                        error,
+                    })
+                }
+            }
+        }
+        let next_page_token = raw["meta"]["nextPageToken"]
+            .as_str()
+            .filter(|token| !token.is_empty())
+            .map(str::to_string);
+        Ok(ActionsPage {
+            url: url.into(),
+            raw,
+            actions,
+            rejected,
+            next_page_token,
+        })
+    }
+
+    /// Drops the actions outside `[from, to)`, in seconds.
+    pub fn retain_between(&mut self, from: i64, to: i64) {
+        self.actions.retain(|a| (from..to).contains(&a.timestamp()));
+        self.rejected.retain(|r| {
+            r.start_time
+                .is_none_or(|start_time| (from..to).contains(&start_time))
+        });
+    }
+}
+
+/// An entry of `/v2/pools`.
+#[derive(Debug, Clone)]
+pub struct PoolDetail {
+    pub asset: String,
+    pub status: String,
+    pub native_decimal: Option<i32>, // Absent when Midgard reports -1 (unknown)
+    pub asset_depth: i64,
+    pub rune_depth: i64,
+    pub asset_price: f64,
+    pub asset_price_usd: f64,
+    pub units: i64,
+    pub liquidity_units: i64,
+    pub synth_units: i64,
+    pub synth_supply: i64,
+    pub volume_24h: i64,
+    // Rates are undefined for pools without enough history, so they are optional.
+    pub annual_percentage_rate: Option<f64>,
+    pub pool_apy: Option<f64>,
+    pub savers_depth: i64,
+    pub savers_units: i64,
+    pub savers_apr: Option<f64>,
+}
+
+impl PoolDetail {
+    pub fn from_value(value: &Value) -> Result<Self, MidgardError> {
+        let native_decimal: i32 = parse_number(value, "nativeDecimal")?;
+        Ok(PoolDetail {
+            asset: parse_string(value, "asset")?,
+            status: parse_string(value, "status")?,
+            native_decimal: Some(native_decimal).filter(|decimals| *decimals >= 0),
+            asset_depth: parse_number(value, "assetDepth")?,
+            rune_depth: parse_number(value, "runeDepth")?,
+            asset_price: parse_f64(value, "assetPrice")?,
+            asset_price_usd: parse_f64(value, "assetPriceUSD")?,
+            units: parse_number(value, "units")?,
+            liquidity_units: parse_number(value, "liquidityUnits")?,
+            synth_units: parse_number(value, "synthUnits")?,
+            synth_supply: parse_number(value, "synthSupply")?,
+            volume_24h: parse_number(value, "volume24h")?,
+            annual_percentage_rate: parse_f64(value, "annualPercentageRate").ok(),
+            pool_apy: parse_f64(value, "poolAPY").ok(),
+            savers_depth: parse_number(value, "saversDepth")?,
+            savers_units: parse_number(value, "saversUnits")?,
+            savers_apr: parse_f64(value, "saversAPR").ok(),
+        })
+    }
+}
+
+/// `/v2/network` at the time it was fetched.
+#[derive(Debug, Clone)]
+pub struct NetworkInfo {
+    pub bonding_apy: f64,
+    pub liquidity_apy: f64,
+    pub total_reserve: i64,
+    pub total_pooled_rune: i64,
+    pub active_node_count: i32,
+    pub standby_node_count: i32,
+    pub active_bonds: Vec<i64>,
+    pub standby_bonds: Vec<i64>,
+    pub next_churn_height: i64,
+}
+
+impl NetworkInfo {
+    pub fn from_value(value: &Value) -> Result<Self, MidgardError> {
+        let bonds = |name: &str| {
+            field(value, name)?
+                .as_array()
+                .ok_or_else(|| MidgardError::MissingField(name.to_string()))?
+                .iter()
+                .map(|bond| {
+                    let raw = bond
+                        .as_str()
+                        .map_or_else(|| bond.to_string(), str::to_string);
+                    raw.parse().map_err(|_| MidgardError::ParseNumber {
+                        field: name.to_string(),
+                        value: raw,
+                    })
+                })
+                .collect::<Result<Vec<i64>, _>>()
+        };
+        Ok(NetworkInfo {
+            bonding_apy: parse_f64(value, "bondingAPY")?,
+            liquidity_apy: parse_f64(value, "liquidityAPY")?,
+            total_reserve: parse_number(value, "totalReserve")?,
+            total_pooled_rune: parse_number(value, "totalPooledRune")?,
+            active_node_count: parse_number(value, "activeNodeCount")?,
+            standby_node_count: parse_number(value, "standbyNodeCount")?,
+            active_bonds: bonds("activeBonds")?,
+            standby_bonds: bonds("standbyBonds")?,
+            next_churn_height: parse_number(value, "nextChurnHeight")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// An interval from 2024-01-01T00:00Z to 01:00Z with each of `keys` set to `value`,
+    /// encoded as a string like Midgard does.
+    fn interval(keys: &[&str], value: &str) -> Value {
+        let mut interval = json!({"startTime": "1704067200", "endTime": "1704070800"});
+        for key in keys {
+            interval[*key] = json!(value);
+        }
+        interval
+    }
+
+    #[test]
+    fn decodes_a_depth_interval() {
+        let mut value = interval(
+            &[
+                "assetDepth",
+                "liquidityUnits",
+                "membersCount",
+                "runeDepth",
+                "synthSupply",
+                "synthUnits",
+                "units",
+            ],
+            "7",
+        );
+        value["assetPrice"] = json!("1.5");
+        value["assetPriceUSD"] = json!("65000.25");
+        value["luvi"] = json!("0.001");
+        let depth = DepthHistoryInterval::from_value(&value).unwrap();
+        assert_eq!((depth.start_time, depth.end_time), (1704067200, 1704070800));
+        assert_eq!((depth.asset_depth, depth.members_count), (7, 7));
+        assert_eq!(depth.asset_price_usd, 65000.25);
+    }
+
+    #[test]
+    fn decodes_a_swaps_interval() {
+        let flows = ["Count", "Volume", "VolumeUSD", "Fees", "AverageSlip"];
+        let sides = [
+            "toAsset",
+            "toRune",
+            "toTrade",
+            "fromTrade",
+            "synthMint",
+            "synthRedeem",
+        ];
+        let mut keys: Vec<String> = sides
+            .iter()
+            .flat_map(|side| flows.iter().map(move |flow| format!("{}{}", side, flow)))
+            .collect();
+        keys.extend(
+            [
+                "totalCount",
+                "totalVolume",
+                "totalVolumeUSD",
+                "totalFees",
+                "averageSlip",
+                "runePriceUSD",
+            ]
+            .map(String::from),
+        );
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let swaps = SwapHistoryInterval::from_value(&interval(&keys, "12")).unwrap();
+        assert_eq!(swaps.total_count, 12);
+        assert_eq!(swaps.synth_redeem_average_slip, 12.0);
+    }
+
+    #[test]
+    fn decodes_an_earnings_interval_with_its_pools() {
+        let mut value = interval(
+            &[
+                "blockRewards",
+                "bondingEarnings",
+                "earnings",
+                "liquidityEarnings",
+                "liquidityFees",
+            ],
+            "100",
+        );
+        value["avgNodeCount"] = json!("99.5");
+        value["runePriceUSD"] = json!("4.2");
+        value["pools"] = json!([{
+            "pool": "BTC.BTC",
+            "assetLiquidityFees": "1",
+            "earnings": "2",
+            "rewards": "3",
+            "runeLiquidityFees": "4",
+            "saverEarning": "5",
+            "totalLiquidityFeesRune": "6",
+        }]);
+        let earnings = EarningsHistoryInterval::from_value(&value).unwrap();
+        assert_eq!(earnings.avg_node_count, 99.5);
+        assert_eq!(earnings.pools.len(), 1);
+        assert_eq!(earnings.pools[0].pool, "BTC.BTC");
+        assert_eq!(earnings.pools[0].total_liquidity_fees_rune, 6);
+    }
+
+    #[test]
+    fn decodes_a_runepool_interval() {
+        let runepool =
+            RunepoolHistoryInterval::from_value(&interval(&["units", "count"], "3")).unwrap();
+        assert_eq!((runepool.units, runepool.count), (3, 3));
+    }
+
+    #[test]
+    fn a_missing_key_is_a_missing_field() {
+        let err = RunepoolHistoryInterval::from_value(&interval(&["units"], "3")).unwrap_err();
+        assert!(matches!(err, MidgardError::MissingField(field) if field == "count"));
+
+        let null_count = json!({"startTime": "1", "endTime": "2", "units": "3", "count": null});
+        let err = RunepoolHistoryInterval::from_value(&null_count).unwrap_err();
+        assert!(matches!(err, MidgardError::MissingField(field) if field == "count"));
+    }
+
+    #[test]
+    fn a_non_numeric_string_is_a_parse_error() {
+        let err = RunepoolHistoryInterval::from_value(&interval(&["units", "count"], "lots"))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            MidgardError::ParseNumber { field, value } if field == "units" && value == "lots"
+        ));
+
+        let err = parse_f64(&json!({"luvi": "NaN"}), "luvi").unwrap_err();
+        assert!(matches!(err, MidgardError::ParseNumber { field, .. } if field == "luvi"));
+    }
+
+    #[test]
+    fn rejected_intervals_do_not_fail_the_page() {
+        let raw = json!({"intervals": [
+            interval(&["units", "count"], "3"),
+            interval(&["units"], "3"),
+        ]});
+        let page =
+            History::<RunepoolHistoryInterval>::from_value("/v2/history/runepool", raw).unwrap();
+        assert_eq!(page.intervals.len(), 1);
+        assert_eq!(page.rejected.len(), 1);
+        assert_eq!(page.rejected[0].start_time, Some(1704067200));
+    }
+}
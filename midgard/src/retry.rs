@@ -0,0 +1,117 @@
+use rand::Rng;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::{sleep_until, Instant};
+
+/// Exponential backoff with full jitter for retryable Midgard responses.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `attempt` (0-based): a random duration up to
+    /// `base_delay * 2^attempt`, capped at `max_delay`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let millis = self.ceiling(attempt).as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0..=millis))
+    }
+
+    /// Delay before retry number `attempt`: the server's `Retry-After` when it sent one,
+    /// otherwise [`RetryPolicy::backoff`]. Either way it is capped at `max_delay`.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        match retry_after {
+            Some(retry_after) => retry_after.min(self.max_delay),
+            None => self.backoff(attempt),
+        }
+    }
+
+    fn ceiling(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+}
+
+/// Spaces requests evenly so that at most `requests_per_second` are started.
+/// Clones of a transport share one limiter, so the budget is global to the client.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn per_second(requests_per_second: f64) -> Self {
+        RateLimiter {
+            interval: Duration::from_secs_f64(1.0 / requests_per_second.max(0.001)),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits for the next free slot and returns how long the caller was throttled.
+    pub async fn acquire(&self) -> Duration {
+        let now = Instant::now();
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let slot = (*next_slot).max(now);
+            *next_slot = slot + self.interval;
+            slot
+        };
+        sleep_until(slot).await;
+        slot - now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_max_delay() {
+        let policy = RetryPolicy::default();
+        let ceilings: Vec<_> = (0..4).map(|attempt| policy.ceiling(attempt)).collect();
+        assert_eq!(ceilings, [500, 1000, 2000, 4000].map(Duration::from_millis));
+        assert_eq!(policy.ceiling(10), policy.max_delay);
+        assert_eq!(policy.ceiling(u32::MAX), policy.max_delay);
+        for attempt in 0..8 {
+            assert!(policy.backoff(attempt) <= policy.ceiling(attempt));
+        }
+    }
+
+    #[test]
+    fn retry_after_is_used_up_to_the_max_delay() {
+        let policy = RetryPolicy::default();
+        let short = Duration::from_secs(2);
+        assert_eq!(policy.delay(0, Some(short)), short);
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_secs(86_400))),
+            policy.max_delay
+        );
+        assert!(policy.delay(3, None) <= policy.ceiling(3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limiter_spaces_requests() {
+        let limiter = RateLimiter::per_second(10.0);
+        let start = Instant::now();
+        let waits = tokio::join!(limiter.acquire(), limiter.acquire(), limiter.acquire());
+        assert_eq!(
+            [waits.0, waits.1, waits.2],
+            [0, 100, 200].map(Duration::from_millis)
+        );
+        assert_eq!(start.elapsed(), Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,308 @@
+use crate::error::MidgardError;
+use crate::retry::{RateLimiter, RetryPolicy};
+use chrono::{DateTime, Utc};
+use reqwest::header::RETRY_AFTER;
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::future::Future;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+
+pub const DEFAULT_BASE_URL: &str = "https://midgard.ninerealms.com";
+pub const DEFAULT_REQUESTS_PER_SECOND: f64 = 5.0;
+
+/// Fetches a Midgard path such as `/v2/history/swaps?interval=hour&from=0&count=400`
+/// and returns the JSON body.
+pub trait Transport: Send + Sync {
+    fn get_json(&self, path: &str) -> impl Future<Output = Result<Value, MidgardError>> + Send;
+
+    /// Where `path` is fetched from, as recorded alongside archived pages.
+    fn url(&self, path: &str) -> String {
+        path.to_string()
+    }
+}
+
+fn env_number<T: FromStr>(name: &str) -> Option<T> {
+    std::env::var(name).ok()?.parse().ok()
+}
+
+/// A wait or failure of a request, reported to the observer set with
+/// [`HttpTransport::with_observer`]. The transport itself does not log.
+#[derive(Debug)]
+pub enum RequestEvent<'a> {
+    /// The request waited `waited` for the requests-per-second budget.
+    Throttled { url: &'a str, waited: Duration },
+    /// Retry number `attempt` of at most `max_retries` starts after `delay`.
+    Retrying {
+        url: &'a str,
+        delay: Duration,
+        attempt: u32,
+        max_retries: u32,
+        error: &'a reqwest::Error,
+    },
+    /// The request failed again after `retries` retries and is given up on.
+    GaveUp { url: &'a str, retries: u32 },
+}
+
+type Observer = Arc<dyn Fn(&RequestEvent<'_>) + Send + Sync>;
+
+/// Talks to a live Midgard instance over HTTP, retrying 429/5xx responses and
+/// connection failures and keeping to a shared requests-per-second budget.
+#[derive(Clone)]
+pub struct HttpTransport {
+    http: reqwest::Client,
+    base_url: String,
+    retry: RetryPolicy,
+    limiter: Arc<RateLimiter>,
+    observer: Option<Observer>,
+}
+
+impl HttpTransport {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        HttpTransport {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            retry: RetryPolicy::default(),
+            limiter: Arc::new(RateLimiter::per_second(DEFAULT_REQUESTS_PER_SECOND)),
+            observer: None,
+        }
+    }
+
+    /// Configured from the environment: `MIDGARD_URL` (defaults to the public ninerealms
+    /// instance), `MIDGARD_REQUESTS_PER_SECOND` and `MIDGARD_MAX_RETRIES`.
+    pub fn from_env() -> Self {
+        let base_url =
+            std::env::var("MIDGARD_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
+        let mut transport = HttpTransport::new(base_url);
+        if let Some(rps) = env_number::<f64>("MIDGARD_REQUESTS_PER_SECOND") {
+            transport = transport.with_requests_per_second(rps);
+        }
+        if let Some(max_retries) = env_number::<u32>("MIDGARD_MAX_RETRIES") {
+            transport = transport.with_retry_policy(RetryPolicy {
+                max_retries,
+                ..RetryPolicy::default()
+            });
+        }
+        transport
+    }
+
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_requests_per_second(mut self, requests_per_second: f64) -> Self {
+        self.limiter = Arc::new(RateLimiter::per_second(requests_per_second));
+        self
+    }
+
+    /// Calls `observer` whenever a request is throttled, retried or given up on.
+    pub fn with_observer(
+        mut self,
+        observer: impl Fn(&RequestEvent<'_>) + Send + Sync + 'static,
+    ) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    fn report(&self, event: RequestEvent<'_>) {
+        if let Some(observer) = &self.observer {
+            observer(&event);
+        }
+    }
+}
+
+impl Default for HttpTransport {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_URL)
+    }
+}
+
+impl Transport for HttpTransport {
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    async fn get_json(&self, path: &str) -> Result<Value, MidgardError> {
+        let url = self.url(path);
+        let mut attempt = 0;
+        loop {
+            let waited = self.limiter.acquire().await;
+            if !waited.is_zero() {
+                self.report(RequestEvent::Throttled { url: &url, waited });
+            }
+
+            let (err, retry_after) = match self.http.get(&url).send().await {
+                Ok(response) => match response.error_for_status_ref() {
+                    Ok(_) => {
+                        let body = response.text().await?;
+                        return Ok(serde_json::from_str(&body)?);
+                    }
+                    Err(err) if is_retryable_status(response.status()) => {
+                        (err, retry_after(&response))
+                    }
+                    Err(err) => return Err(err.into()),
+                },
+                Err(err) if err.is_timeout() || err.is_connect() => (err, None),
+                Err(err) => return Err(err.into()),
+            };
+
+            if attempt >= self.retry.max_retries {
+                self.report(RequestEvent::GaveUp {
+                    url: &url,
+                    retries: attempt,
+                });
+                return Err(err.into());
+            }
+            let delay = self.retry.delay(attempt, retry_after);
+            attempt += 1;
+            self.report(RequestEvent::Retrying {
+                url: &url,
+                delay,
+                attempt,
+                max_retries: self.retry.max_retries,
+                error: &err,
+            });
+            sleep(delay).await;
+        }
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Reads `Retry-After` as either delay-seconds or an HTTP date.
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&Utc) - Utc::now()).to_std().ok()
+}
+
+/// Replays JSON pages recorded on disk, so ingestion can run without the network.
+///
+/// Each request path maps to one file in `dir`, named by [`FixtureTransport::file_name`].
+#[derive(Clone)]
+pub struct FixtureTransport {
+    dir: PathBuf,
+}
+
+impl FixtureTransport {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FixtureTransport { dir: dir.into() }
+    }
+
+    /// `/v2/history/swaps?interval=hour&from=0&count=400` becomes
+    /// `v2_history_swaps_interval_hour_from_0_count_400.json`.
+    pub fn file_name(path: &str) -> String {
+        let name = path
+            .trim_start_matches('/')
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+        format!("{}.json", name)
+    }
+}
+
+impl Transport for FixtureTransport {
+    async fn get_json(&self, path: &str) -> Result<Value, MidgardError> {
+        let file = self.dir.join(Self::file_name(path));
+        let body = std::fs::read_to_string(&file).map_err(|err| MidgardError::Fixture {
+            path: file.display().to_string(),
+            source: err,
+        })?;
+        Ok(serde_json::from_str(&body)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::time::timeout;
+
+    const TOO_MANY_REQUESTS: &str = "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 86400\r\n\
+        Content-Length: 0\r\nConnection: close\r\n\r\n";
+    const SERVER_ERROR: &str = "HTTP/1.1 503 Service Unavailable\r\n\
+        Content-Length: 0\r\nConnection: close\r\n\r\n";
+    const OK: &str = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+        Content-Length: 11\r\nConnection: close\r\n\r\n{\"ok\":true}";
+
+    /// Answers one request per connection with `responses`, in order, and returns the base
+    /// URL to reach it at.
+    async fn stub(responses: Vec<&'static str>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0; 4096];
+                let _ = socket.read(&mut request).await;
+                socket.write_all(response.as_bytes()).await.unwrap();
+                let _ = socket.shutdown().await;
+            }
+        });
+        base_url
+    }
+
+    fn transport(base_url: String, max_retries: u32) -> HttpTransport {
+        HttpTransport::new(base_url)
+            .with_requests_per_second(1000.0)
+            .with_retry_policy(RetryPolicy {
+                max_retries,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(50),
+            })
+    }
+
+    #[tokio::test]
+    async fn caps_retry_after_at_the_max_delay() {
+        let base_url = stub(vec![TOO_MANY_REQUESTS, OK]).await;
+        let delays = Arc::new(Mutex::new(Vec::new()));
+        let transport = transport(base_url, 1).with_observer({
+            let delays = delays.clone();
+            move |event| {
+                if let RequestEvent::Retrying { delay, .. } = event {
+                    delays.lock().unwrap().push(*delay);
+                }
+            }
+        });
+        let body = timeout(Duration::from_secs(5), transport.get_json("/v2/health"))
+            .await
+            .expect("Retry-After: 86400 is not waited for")
+            .unwrap();
+        assert_eq!(body, serde_json::json!({"ok": true}));
+        assert_eq!(*delays.lock().unwrap(), [Duration::from_millis(50)]);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let base_url = stub(vec![SERVER_ERROR, SERVER_ERROR]).await;
+        let gave_up = Arc::new(Mutex::new(None));
+        let transport = transport(base_url, 1).with_observer({
+            let gave_up = gave_up.clone();
+            move |event| {
+                if let RequestEvent::GaveUp { retries, .. } = event {
+                    *gave_up.lock().unwrap() = Some(*retries);
+                }
+            }
+        });
+        let result = transport.get_json("/v2/health").await;
+        assert!(matches!(result, Err(MidgardError::Http(_))));
+        assert_eq!(*gave_up.lock().unwrap(), Some(1));
+    }
+}
@@ -0,0 +1,8 @@
+-- Track depth/price history per pool instead of a single BTC.BTC series
+ALTER TABLE Depth_Price_History ADD COLUMN pool TEXT NOT NULL DEFAULT 'BTC.BTC';  -- Existing rows were all fetched for BTC.BTC
+ALTER TABLE Depth_Price_History ALTER COLUMN pool DROP DEFAULT;
+
+ALTER TABLE Depth_Price_History DROP CONSTRAINT depth_price_history_starttime_endtime_key;
+ALTER TABLE Depth_Price_History
+    ADD CONSTRAINT depth_price_history_pool_starttime_endtime_key
+    UNIQUE (pool, startTime, endTime);                                            -- Each pool has one record per time interval
@@ -0,0 +1,22 @@
+-- Resume point of each ingestion dataset, written in the same transaction as the page it covers
+CREATE TABLE ingestion_state (
+    dataset TEXT NOT NULL,                         -- Target table of the dataset, e.g. swap_history
+    pool TEXT NOT NULL DEFAULT '',                 -- Pool for per-pool datasets, '' for network-wide ones
+    last_end_time BIGINT NOT NULL,                 -- endTime of the last ingested interval (UNIX seconds)
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), -- When the checkpoint last moved
+    PRIMARY KEY (dataset, pool)
+);
+
+-- Carry over progress of existing installs so they don't re-fetch history
+INSERT INTO ingestion_state (dataset, pool, last_end_time)
+SELECT 'depth_price_history', pool, MAX(endTime) FROM Depth_Price_History GROUP BY pool
+HAVING MAX(endTime) IS NOT NULL;
+
+INSERT INTO ingestion_state (dataset, pool, last_end_time)
+SELECT 'swap_history', '', MAX(endTime) FROM swap_history HAVING MAX(endTime) IS NOT NULL;
+
+INSERT INTO ingestion_state (dataset, pool, last_end_time)
+SELECT 'runepool_history', '', MAX(endTime) FROM runepool_history HAVING MAX(endTime) IS NOT NULL;
+
+INSERT INTO ingestion_state (dataset, pool, last_end_time)
+SELECT 'earning_history', '', MAX(endTime) FROM earning_history HAVING MAX(endTime) IS NOT NULL;
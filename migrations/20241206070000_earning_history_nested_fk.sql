@@ -0,0 +1,23 @@
+-- Key earnings pool rows by their parent interval instead of listing their ids in earning_history.pools
+ALTER TABLE earning_history_nested
+    ADD COLUMN earning_history_id INTEGER REFERENCES earning_history (id) ON DELETE CASCADE;
+
+UPDATE earning_history_nested en
+SET earning_history_id = eh.id
+FROM earning_history eh
+WHERE en.id = ANY (eh.pools);
+
+-- Rows orphaned by re-runs that never got a parent, and duplicates within an interval
+DELETE FROM earning_history_nested WHERE earning_history_id IS NULL OR pool IS NULL;
+DELETE FROM earning_history_nested a
+USING earning_history_nested b
+WHERE a.earning_history_id = b.earning_history_id
+  AND a.pool = b.pool
+  AND a.id > b.id;
+
+ALTER TABLE earning_history_nested DROP COLUMN id;
+ALTER TABLE earning_history_nested ALTER COLUMN earning_history_id SET NOT NULL;
+ALTER TABLE earning_history_nested ALTER COLUMN pool SET NOT NULL;
+ALTER TABLE earning_history_nested ADD PRIMARY KEY (earning_history_id, pool);
+
+ALTER TABLE earning_history DROP COLUMN pools;
@@ -0,0 +1,28 @@
+-- Intervals still accumulating when ingested are provisional and get overwritten on later runs;
+-- once Midgard reports them finalized they become immutable.
+ALTER TABLE Depth_Price_History ADD COLUMN provisional BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE swap_history ADD COLUMN provisional BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE runepool_history ADD COLUMN provisional BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE earning_history ADD COLUMN provisional BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- The newest interval of each dataset was stored while still in progress: reopen it and move the
+-- checkpoint back so the next run refreshes it.
+UPDATE Depth_Price_History d SET provisional = TRUE
+FROM (SELECT pool, MAX(endTime) AS endTime FROM Depth_Price_History GROUP BY pool) latest
+WHERE d.pool = latest.pool AND d.endTime = latest.endTime;
+UPDATE swap_history SET provisional = TRUE WHERE endTime = (SELECT MAX(endTime) FROM swap_history);
+UPDATE runepool_history SET provisional = TRUE WHERE endTime = (SELECT MAX(endTime) FROM runepool_history);
+UPDATE earning_history SET provisional = TRUE WHERE endTime = (SELECT MAX(endTime) FROM earning_history);
+
+UPDATE ingestion_state s SET last_end_time = d.startTime
+FROM Depth_Price_History d
+WHERE s.dataset = 'depth_price_history' AND s.pool = d.pool AND d.provisional;
+UPDATE ingestion_state s SET last_end_time = t.startTime
+FROM swap_history t
+WHERE s.dataset = 'swap_history' AND t.provisional;
+UPDATE ingestion_state s SET last_end_time = t.startTime
+FROM runepool_history t
+WHERE s.dataset = 'runepool_history' AND t.provisional;
+UPDATE ingestion_state s SET last_end_time = t.startTime
+FROM earning_history t
+WHERE s.dataset = 'earning_history' AND t.provisional;
@@ -0,0 +1,11 @@
+-- Every history page fetched from Midgard, kept as returned so typed tables can be rebuilt offline
+CREATE TABLE raw_midgard_pages (
+    id BIGSERIAL PRIMARY KEY,
+    dataset TEXT NOT NULL,                          -- Target table of the page, e.g. swap_history
+    pool TEXT NOT NULL DEFAULT '',                  -- Pool for per-pool datasets, '' for network-wide ones
+    url TEXT NOT NULL,                              -- URL the page was fetched from
+    body JSONB NOT NULL,                            -- Response body as returned by Midgard
+    fetched_at TIMESTAMPTZ NOT NULL DEFAULT NOW()   -- When the page was fetched
+);
+
+CREATE INDEX raw_midgard_pages_dataset_id_idx ON raw_midgard_pages (dataset, id);
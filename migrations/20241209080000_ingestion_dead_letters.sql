@@ -0,0 +1,21 @@
+-- Intervals Midgard returned that could not be decoded or validated; the rest of their page is ingested
+CREATE TABLE ingestion_dead_letters (
+    id BIGSERIAL PRIMARY KEY,
+    dataset TEXT NOT NULL,                            -- Target table of the interval, e.g. swap_history
+    pool TEXT NOT NULL DEFAULT '',                    -- Pool for per-pool datasets, '' for network-wide ones
+    start_time BIGINT,                                -- startTime of the interval, NULL if unreadable
+    end_time BIGINT,                                  -- endTime of the interval, NULL if unreadable
+    url TEXT NOT NULL,                                -- URL of the page the interval came from
+    payload JSONB NOT NULL,                           -- The interval as returned by Midgard
+    error TEXT NOT NULL,                              -- Why it was rejected (latest attempt)
+    attempts INT NOT NULL DEFAULT 1,                  -- How many times the same payload was rejected
+    first_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ                           -- Set once the interval is ingested
+);
+
+-- The same payload seen again bumps attempts instead of adding a row
+CREATE UNIQUE INDEX ingestion_dead_letters_payload_key
+    ON ingestion_dead_letters (dataset, pool, md5(payload::text));
+CREATE INDEX ingestion_dead_letters_open_idx
+    ON ingestion_dead_letters (dataset, pool, start_time) WHERE resolved_at IS NULL;
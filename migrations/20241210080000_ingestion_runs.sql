@@ -0,0 +1,20 @@
+-- One row per ingestion run of a dataset: scheduled syncs, backfills, dead letter retries and reprocessing
+CREATE TABLE ingestion_runs (
+    id BIGSERIAL PRIMARY KEY,
+    dataset TEXT NOT NULL,                          -- Target table of the run, e.g. swap_history
+    kind TEXT NOT NULL,                             -- sync, backfill, retry or reprocess
+    pool TEXT,                                      -- Pool the run was limited to, if any
+    range_from BIGINT,                              -- Requested range start (UNIX seconds), NULL for syncs
+    range_to BIGINT,                                -- Requested range end (UNIX seconds), NULL for syncs
+    status TEXT NOT NULL DEFAULT 'running',         -- running, succeeded or failed
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ,
+    pages_fetched INT NOT NULL DEFAULT 0,           -- Midgard pages fetched (or replayed from the archive)
+    rows_inserted BIGINT NOT NULL DEFAULT 0,        -- Intervals stored for the first time
+    rows_updated BIGINT NOT NULL DEFAULT 0,         -- Provisional intervals refreshed
+    rows_skipped BIGINT NOT NULL DEFAULT 0,         -- Intervals already stored and finalized
+    rows_rejected BIGINT NOT NULL DEFAULT 0,        -- Intervals sent to ingestion_dead_letters
+    error TEXT                                      -- Why the run failed
+);
+
+CREATE INDEX ingestion_runs_dataset_started_at_idx ON ingestion_runs (dataset, started_at DESC);
@@ -0,0 +1,4 @@
+-- Runs requested through the admin API wait as 'queued' until the dataset's leader picks them up
+ALTER TABLE ingestion_runs ADD COLUMN queued_at TIMESTAMPTZ; -- When the run was requested, NULL for runs started directly
+
+CREATE INDEX ingestion_runs_queued_idx ON ingestion_runs (dataset, id) WHERE status = 'queued';
@@ -0,0 +1,19 @@
+-- Hourly intervals of /v2/history/tvl, with the depth of every pool in its own table
+CREATE TABLE tvl_history (
+    id SERIAL PRIMARY KEY,
+    startTime BIGINT NOT NULL,
+    endTime BIGINT NOT NULL,
+    totalValuePooled BIGINT,
+    totalValueBonded BIGINT,
+    totalValueLocked BIGINT,
+    runePriceUsd DOUBLE PRECISION,
+    provisional BOOLEAN NOT NULL DEFAULT FALSE,
+    UNIQUE (startTime, endTime)
+);
+
+CREATE TABLE tvl_history_pools (
+    tvl_history_id INTEGER NOT NULL REFERENCES tvl_history (id) ON DELETE CASCADE,
+    pool TEXT NOT NULL,
+    totalDepth BIGINT,
+    PRIMARY KEY (tvl_history_id, pool)
+);
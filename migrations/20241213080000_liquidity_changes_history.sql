@@ -0,0 +1,23 @@
+-- Hourly intervals of /v2/history/liquidity_changes for every pool; pool '' holds the
+-- network-wide totals
+CREATE TABLE liquidity_changes_history (
+    id SERIAL PRIMARY KEY,
+    pool TEXT NOT NULL DEFAULT '',
+    startTime BIGINT NOT NULL,
+    endTime BIGINT NOT NULL,
+    addAssetLiquidityVolume BIGINT,
+    addRuneLiquidityVolume BIGINT,
+    addLiquidityVolume BIGINT,
+    addLiquidityVolumeUsd DOUBLE PRECISION,
+    addLiquidityCount BIGINT,
+    removeAssetLiquidityVolume BIGINT,
+    removeRuneLiquidityVolume BIGINT,
+    removeLiquidityVolume BIGINT,
+    removeLiquidityVolumeUsd DOUBLE PRECISION,
+    removeLiquidityCount BIGINT,
+    netChange BIGINT,
+    netChangeUsd DOUBLE PRECISION,
+    runePriceUsd DOUBLE PRECISION,
+    provisional BOOLEAN NOT NULL DEFAULT FALSE,
+    UNIQUE (pool, startTime, endTime)
+);
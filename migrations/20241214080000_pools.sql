@@ -0,0 +1,45 @@
+-- Snapshots of /v2/pools: every pool's state each time it was ingested, and the latest one
+CREATE TABLE pool_snapshots (
+    id BIGSERIAL PRIMARY KEY,
+    snapshotTime BIGINT NOT NULL,          -- UNIX timestamp (seconds) of the fetch
+    asset TEXT NOT NULL,
+    status TEXT NOT NULL,                  -- available, staged, suspended
+    nativeDecimal INTEGER,
+    assetDepth BIGINT,
+    runeDepth BIGINT,
+    assetPrice DOUBLE PRECISION,
+    assetPriceUsd DOUBLE PRECISION,
+    units BIGINT,
+    liquidityUnits BIGINT,
+    synthUnits BIGINT,
+    synthSupply BIGINT,
+    volume24h BIGINT,
+    annualPercentageRate DOUBLE PRECISION,
+    poolApy DOUBLE PRECISION,
+    saversDepth BIGINT,
+    saversUnits BIGINT,
+    saversApr DOUBLE PRECISION,
+    UNIQUE (asset, snapshotTime)
+);
+
+CREATE TABLE pools (
+    asset TEXT PRIMARY KEY,
+    status TEXT NOT NULL,
+    nativeDecimal INTEGER,
+    assetDepth BIGINT,
+    runeDepth BIGINT,
+    assetPrice DOUBLE PRECISION,
+    assetPriceUsd DOUBLE PRECISION,
+    units BIGINT,
+    liquidityUnits BIGINT,
+    synthUnits BIGINT,
+    synthSupply BIGINT,
+    volume24h BIGINT,
+    annualPercentageRate DOUBLE PRECISION,
+    poolApy DOUBLE PRECISION,
+    saversDepth BIGINT,
+    saversUnits BIGINT,
+    saversApr DOUBLE PRECISION,
+    firstSeen BIGINT NOT NULL,             -- First snapshot listing the pool
+    lastSeen BIGINT NOT NULL               -- Latest snapshot listing the pool
+);
@@ -0,0 +1,23 @@
+-- Individual actions (swaps, adds, withdraws, ...) from /v2/actions
+CREATE TABLE actions (
+    id BIGSERIAL PRIMARY KEY,
+    date BIGINT NOT NULL,                   -- UNIX timestamp in nanoseconds, as returned by Midgard
+    height BIGINT NOT NULL,
+    type TEXT NOT NULL,                     -- swap, addLiquidity, withdraw, refund, ...
+    status TEXT NOT NULL,                   -- success or pending
+    txid TEXT NOT NULL,                     -- First inbound tx id, empty when there is none
+    pools TEXT[] NOT NULL,
+    in_addresses TEXT[] NOT NULL,
+    out_addresses TEXT[] NOT NULL,
+    tx_ids TEXT[] NOT NULL,                 -- Inbound and outbound tx ids
+    in_coins JSONB NOT NULL,                -- [{"address", "txID", "coins": [{"asset", "amount"}]}]
+    out_coins JSONB NOT NULL,
+    metadata JSONB,
+    UNIQUE (date, type, txid)
+);
+
+CREATE INDEX actions_date_idx ON actions (date);
+CREATE INDEX actions_pools_idx ON actions USING GIN (pools);
+CREATE INDEX actions_in_addresses_idx ON actions USING GIN (in_addresses);
+CREATE INDEX actions_out_addresses_idx ON actions USING GIN (out_addresses);
+CREATE INDEX actions_tx_ids_idx ON actions USING GIN (tx_ids);
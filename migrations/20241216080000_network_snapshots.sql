@@ -0,0 +1,15 @@
+-- Snapshots of /v2/network, taken on the dataset's schedule
+CREATE TABLE network_snapshots (
+    id BIGSERIAL PRIMARY KEY,
+    snapshotTime BIGINT NOT NULL UNIQUE,   -- UNIX timestamp (seconds) of the fetch
+    bondingApy DOUBLE PRECISION,
+    liquidityApy DOUBLE PRECISION,
+    totalReserve BIGINT,
+    totalPooledRune BIGINT,
+    activeNodeCount INTEGER,
+    standbyNodeCount INTEGER,
+    activeBonds BIGINT[],                  -- Bond of every active node
+    totalActiveBond BIGINT,
+    totalStandbyBond BIGINT,
+    nextChurnHeight BIGINT
+);
@@ -0,0 +1,12 @@
+-- Hourly intervals of /v2/history/savers for every pool
+CREATE TABLE savers_history (
+    id SERIAL PRIMARY KEY,
+    pool TEXT NOT NULL,
+    startTime BIGINT NOT NULL,
+    endTime BIGINT NOT NULL,
+    saversCount BIGINT,
+    saversDepth BIGINT,                    -- In units of the pool's asset
+    saversUnits BIGINT,
+    provisional BOOLEAN NOT NULL DEFAULT FALSE,
+    UNIQUE (pool, startTime, endTime)
+);
@@ -0,0 +1,8 @@
+-- Hours of a gap that Midgard returned no interval for when it was re-fetched; the gap scan skips them
+CREATE TABLE ingestion_empty_hours (
+    dataset TEXT NOT NULL,                            -- Target table of the interval, e.g. swap_history
+    pool TEXT NOT NULL DEFAULT '',                    -- Pool for per-pool datasets, '' for network-wide ones
+    start_time BIGINT NOT NULL,                       -- startTime of the missing interval
+    checked_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (dataset, pool, start_time)
+);
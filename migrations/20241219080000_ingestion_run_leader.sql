@@ -0,0 +1,2 @@
+-- Runs started by a dataset's leader record its lock session, so a new leader only interrupts the runs of the one before it
+ALTER TABLE ingestion_runs ADD COLUMN leader_pid INTEGER; -- Backend pid of the leader's lock session, NULL for runs started from the CLI
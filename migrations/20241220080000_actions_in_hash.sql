@@ -0,0 +1,6 @@
+-- Actions without an inbound tx id share an empty txid, so the key also tells them apart by what they received.
+-- Only the inbound side is hashed: the outbound one is filled in when a pending action completes.
+ALTER TABLE actions ADD COLUMN in_hash TEXT GENERATED ALWAYS AS (md5(in_coins::text)) STORED;
+
+ALTER TABLE actions DROP CONSTRAINT actions_date_type_txid_key;
+ALTER TABLE actions ADD CONSTRAINT actions_date_type_txid_in_hash_key UNIQUE (date, type, txid, in_hash);
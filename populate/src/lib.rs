@@ -1,5 +1,7 @@
-
 pub mod scripts;
-pub mod models;
-pub use models::*;
-pub use scripts::cron_job::start_cron_job;
\ No newline at end of file
+pub use scripts::cron_job::{start_cron_job, SchedulerState};
+pub use scripts::dataset::Dataset;
+pub use scripts::dead_letters::retry_dead_letter;
+pub use scripts::error::IngestError;
+pub use scripts::gaps::{find_gaps, refetch_gaps, Gap};
+pub use scripts::runs::{RunKind, RunStats};
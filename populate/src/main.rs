@@ -1,15 +1,215 @@
-
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{Parser, Subcommand};
+use midgard::{HttpTransport, MidgardClient, RequestEvent};
+use populate::scripts::leader::lock_dataset;
+use populate::scripts::runs;
+use populate::{find_gaps, start_cron_job, Dataset, RunKind, SchedulerState};
 use shared::{create_db_pool, run_migrations};
-use std::sync::Arc;
-use populate::scripts::cron_job::start_cron_job;
+use std::time::Duration;
+
+// Waits for the request budget shorter than this are not printed.
+const THROTTLE_LOG_THRESHOLD: Duration = Duration::from_secs(1);
+
+#[derive(Parser)]
+#[command(about = "Mirrors Midgard history endpoints into Postgres")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Apply pending migrations and exit
+    Migrate,
+    /// Re-fetch a time range of one dataset, leaving its checkpoint untouched
+    Backfill {
+        #[arg(long)]
+        dataset: Dataset,
+        /// UNIX seconds, `YYYY-MM-DD` or RFC 3339
+        #[arg(long, value_parser = parse_timestamp)]
+        from: i64,
+        /// Defaults to now
+        #[arg(long, value_parser = parse_timestamp)]
+        to: Option<i64>,
+        /// Only this pool, for per-pool datasets
+        #[arg(long)]
+        pool: Option<String>,
+    },
+    /// Run every dataset (or one) once from its checkpoint and exit
+    SyncOnce {
+        #[arg(long)]
+        dataset: Option<Dataset>,
+    },
+    /// List the hours missing between the first stored interval and the checkpoint
+    VerifyGaps {
+        /// Re-fetch the gaps that are found
+        #[arg(long)]
+        fix: bool,
+        #[arg(long)]
+        dataset: Option<Dataset>,
+        #[arg(long, value_parser = parse_timestamp)]
+        from: Option<i64>,
+        #[arg(long, value_parser = parse_timestamp)]
+        to: Option<i64>,
+    },
+    /// Rewrite stored intervals from the archived Midgard pages, without the network
+    Reprocess {
+        #[arg(long)]
+        dataset: Option<Dataset>,
+    },
+    /// Run migrations, then every dataset on its schedule (the default)
+    Cron,
+}
+
+fn parse_timestamp(value: &str) -> Result<i64, String> {
+    if let Ok(seconds) = value.parse::<i64>() {
+        return Ok(seconds);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.timestamp())
+        .map_err(|_| format!("Invalid timestamp `{}`", value))
+}
+
+fn datasets(dataset: Option<Dataset>) -> Vec<Dataset> {
+    match dataset {
+        Some(dataset) => vec![dataset],
+        None => Dataset::ALL.to_vec(),
+    }
+}
+
+fn print_request_event(event: &RequestEvent<'_>) {
+    match event {
+        RequestEvent::Throttled { url, waited } if *waited >= THROTTLE_LOG_THRESHOLD => {
+            println!("Throttled {:?} for {:?}", url, waited);
+        }
+        RequestEvent::Throttled { .. } => {}
+        RequestEvent::Retrying {
+            url,
+            delay,
+            attempt,
+            max_retries,
+            error,
+        } => eprintln!(
+            "Retrying {:?} in {:?} (attempt {}/{}): {}",
+            url, delay, attempt, max_retries, error
+        ),
+        RequestEvent::GaveUp { url, retries } => {
+            eprintln!("Giving up on {:?} after {} retries", url, retries);
+        }
+    }
+}
+
+/// The Midgard client configured from the environment, printing throttling and retries.
+fn midgard_client() -> MidgardClient {
+    MidgardClient::with_transport(HttpTransport::from_env().with_observer(print_request_event))
+}
+
 #[tokio::main]
 async fn main() {
+    // Errors are printed with Display: returning them from main would print their Debug.
+    if let Err(err) = run(Cli::parse()).await {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
     let pool = create_db_pool().await.expect(
         "Failed to create database pool. Ensure the database server is running and accessible.",
     );
-    let pool = Arc::new(pool);
-
-    run_migrations(&*pool.lock().await).await.unwrap();
 
-    start_cron_job(&pool).await;
+    match cli.command.unwrap_or(Command::Cron) {
+        Command::Migrate => run_migrations(&pool).await?,
+        Command::Backfill {
+            dataset,
+            from,
+            to,
+            pool: pool_name,
+        } => {
+            let to = to.unwrap_or_else(|| Utc::now().timestamp());
+            println!("Backfilling {} from {} to {}", dataset, from, to);
+            let _lock = lock_dataset(&pool, dataset).await?;
+            let client = midgard_client();
+            let stats = runs::backfill(
+                &pool,
+                &client,
+                dataset,
+                RunKind::Backfill,
+                pool_name.as_deref(),
+                from,
+                to,
+            )
+            .await?;
+            println!("{:?}", stats);
+        }
+        Command::SyncOnce { dataset } => {
+            let client = midgard_client();
+            for dataset in datasets(dataset) {
+                println!("Running {}", dataset);
+                let _lock = lock_dataset(&pool, dataset).await?;
+                let stats = runs::sync(&pool, &client, dataset, None).await?;
+                println!("{}: {:?}", dataset, stats);
+            }
+        }
+        Command::VerifyGaps {
+            fix,
+            dataset,
+            from,
+            to,
+        } => {
+            let mut gaps = Vec::new();
+            for dataset in datasets(dataset) {
+                gaps.extend(find_gaps(&pool, dataset, from, to).await?);
+            }
+            for gap in &gaps {
+                println!(
+                    "{} {} missing {} to {} ({} hours)",
+                    gap.dataset,
+                    gap.pool.as_deref().unwrap_or("-"),
+                    gap.start_time,
+                    gap.end_time,
+                    (gap.end_time - gap.start_time) / 3600
+                );
+            }
+            println!("Found {} gaps", gaps.len());
+            if fix {
+                let client = midgard_client();
+                for gap in &gaps {
+                    let _lock = lock_dataset(&pool, gap.dataset).await?;
+                    runs::backfill(
+                        &pool,
+                        &client,
+                        gap.dataset,
+                        RunKind::Backfill,
+                        gap.pool.as_deref(),
+                        gap.start_time,
+                        gap.end_time,
+                    )
+                    .await?;
+                }
+            }
+        }
+        Command::Reprocess { dataset: selected } => {
+            // Snapshot datasets have nothing to replay unless asked for explicitly.
+            for dataset in datasets(selected) {
+                if selected.is_none() && !dataset.has_history() {
+                    continue;
+                }
+                let _lock = lock_dataset(&pool, dataset).await?;
+                let stats = runs::reprocess(&pool, dataset).await?;
+                println!(
+                    "Replayed {} archived pages into {}",
+                    stats.pages_fetched, dataset
+                );
+            }
+        }
+        Command::Cron => {
+            run_migrations(&pool).await?;
+            start_cron_job(pool, midgard_client(), SchedulerState::default()).await;
+        }
+    }
+    Ok(())
 }
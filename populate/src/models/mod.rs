@@ -1,4 +0,0 @@
-pub mod depth_price_history;
-pub mod earnings_history;
-pub mod runepool_history;
-pub mod swaps_history;
\ No newline at end of file
@@ -0,0 +1,228 @@
+use super::archive::{archive_body, archived_pages};
+use super::dead_letters::{replay_dead_letters, resolve_dead_letters, save_dead_letters};
+use super::error::IngestError;
+use super::runs::RunStats;
+use super::utils::{collect_column, get_checkpoint, save_checkpoint};
+use chrono::Utc;
+use midgard::{Action, ActionTransfer, ActionsPage, MidgardClient, Transport};
+use serde_json::{json, Value};
+use sqlx::{PgConnection, PgPool};
+
+const DATASET: &str = "actions";
+
+// Midgard returns at most 50 actions per page.
+const PAGE_SIZE: u32 = 50;
+
+// Width (seconds) of the windows a range of actions is paged in.
+const WINDOW: i64 = 86_400;
+
+// How far (seconds) before the end of a range a pending action still holds the checkpoint
+// back. One that never completes would otherwise have every run re-page from its date.
+const PENDING_HOLD: i64 = 3 * 86_400;
+
+pub async fn fetch_and_insert_data<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let from_time = get_checkpoint(pool, DATASET, None).await?;
+    let to = Utc::now().timestamp() + 1;
+    fetch_and_insert_range(pool, client, from_time, to, true, stats).await
+}
+
+/// Ingests the actions dated in `[from, to)` (seconds). Pages come newest first, so the
+/// range is split into windows that are paged oldest first. With `save_progress` the
+/// checkpoint moves after each window, to its end or to the newest action of the last
+/// one, but never past the oldest action still pending so that its outcome is picked up
+/// by the next run. Actions pending since more than [`PENDING_HOLD`] before `to` are given
+/// up on. An interrupted run resumes from the last window stored.
+pub async fn fetch_and_insert_range<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    from: i64,
+    to: i64,
+    save_progress: bool,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let mut oldest_pending: Option<i64> = None;
+    let mut window_from = from;
+    while window_from < to {
+        let window_to = (window_from + WINDOW).min(to);
+        let (newest, pending) =
+            fetch_and_insert_window(pool, client, window_from, window_to, stats).await?;
+        // Windows go oldest first, so the first pending action found is the oldest.
+        oldest_pending = oldest_pending.or(pending.filter(|date| *date >= to - PENDING_HOLD));
+
+        // A sync's last window ends now, where Midgard may still be adding actions, so only
+        // the actions it returned are known to be stored.
+        let stored = if window_to < to {
+            Some(window_to)
+        } else {
+            newest
+        };
+        if let Some(stored) = stored.filter(|_| save_progress) {
+            let checkpoint = oldest_pending.map_or(stored, |pending| pending.min(stored));
+            let mut tx = pool.begin().await?;
+            save_checkpoint(&mut tx, DATASET, None, checkpoint).await?;
+            tx.commit().await?;
+        }
+        window_from = window_to;
+    }
+    Ok(())
+}
+
+/// Pages through the actions dated in `[from, to)`, newest first. Returns the dates of the
+/// newest action and of the oldest one still pending.
+async fn fetch_and_insert_window<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    from: i64,
+    to: i64,
+    stats: &mut RunStats,
+) -> Result<(Option<i64>, Option<i64>), IngestError> {
+    let mut next_page_token: Option<String> = None;
+    let mut newest: Option<i64> = None;
+    let mut oldest_pending: Option<i64> = None;
+    loop {
+        let mut page = client
+            .actions(Some(from), Some(to), next_page_token.as_deref(), PAGE_SIZE)
+            .await?;
+        let mut tx = pool.begin().await?;
+        archive_body(&mut tx, DATASET, None, &page.url, &page.raw).await?;
+        if page.actions.is_empty() && page.rejected.is_empty() {
+            break;
+        }
+        let reached_from = page.actions.iter().any(|a| a.timestamp() < from);
+        page.retain_between(from, to);
+        save_dead_letters(&mut tx, DATASET, None, &page.url, &page.rejected).await?;
+
+        let written = insert_page(&mut tx, &page.actions, false).await?;
+        stats.add_page(page.actions.len(), &written, page.rejected.len());
+        let start_times = collect_column(&page.actions, |a| a.timestamp());
+        resolve_dead_letters(&mut tx, DATASET, None, &start_times).await?;
+        tx.commit().await?;
+
+        for action in &page.actions {
+            newest = newest.max(Some(action.timestamp()));
+            if action.status == "pending" {
+                let date = action.timestamp();
+                oldest_pending = Some(oldest_pending.map_or(date, |oldest| oldest.min(date)));
+            }
+        }
+        next_page_token = page.next_page_token;
+        if reached_from || next_page_token.is_none() {
+            break;
+        }
+    }
+    Ok((newest, oldest_pending))
+}
+
+/// Rewrites the stored actions from the archived pages without calling Midgard,
+/// replaying them in the order they were fetched. Successful actions are rewritten too.
+pub async fn reprocess(pool: &PgPool, stats: &mut RunStats) -> Result<(), IngestError> {
+    let mut after_id = 0;
+    loop {
+        let pages = archived_pages(pool, DATASET, after_id).await?;
+        let Some(last_page) = pages.last() else {
+            break;
+        };
+        after_id = last_page.id;
+
+        for archived in pages {
+            let page = ActionsPage::from_value(archived.url, archived.body)?;
+            let start_times = collect_column(&page.actions, |a| a.timestamp());
+            let mut tx = pool.begin().await?;
+            let written = insert_page(&mut tx, &page.actions, true).await?;
+            stats.add_page(page.actions.len(), &written, page.rejected.len());
+            replay_dead_letters(&mut tx, DATASET, None, &page.url, &page.rejected).await?;
+            resolve_dead_letters(&mut tx, DATASET, None, &start_times).await?;
+            tx.commit().await?;
+        }
+    }
+    Ok(())
+}
+
+fn transfers_json(transfers: &[ActionTransfer]) -> Value {
+    transfers
+        .iter()
+        .map(|transfer| {
+            json!({
+                "address": transfer.address,
+                "txID": transfer.tx_id,
+                "coins": transfer.coins.iter().map(|coin| json!({
+                    "asset": coin.asset,
+                    "amount": coin.amount,
+                })).collect::<Vec<_>>(),
+            })
+        })
+        .collect()
+}
+
+fn non_empty(values: impl Iterator<Item = String>) -> Value {
+    values.filter(|value| !value.is_empty()).collect()
+}
+
+/// Upserts a page of actions. Pending actions are refreshed until they succeed, after
+/// which they are left as they are, unless the page is an archived one being `replay`ed.
+/// Returns one flag per row written, `true` when it was inserted rather than updated.
+///
+/// The array columns are bound as JSONB, since `UNNEST` flattens a `TEXT[][]` parameter.
+async fn insert_page(
+    conn: &mut PgConnection,
+    actions: &[Action],
+    replay: bool,
+) -> Result<Vec<bool>, sqlx::Error> {
+    sqlx::query_scalar(
+        "
+    INSERT INTO actions (
+        date, height, type, status, txid, pools, in_addresses, out_addresses, tx_ids,
+        in_coins, out_coins, metadata
+    )
+    SELECT date, height, type, status, txid,
+        ARRAY(SELECT jsonb_array_elements_text(pools)),
+        ARRAY(SELECT jsonb_array_elements_text(in_addresses)),
+        ARRAY(SELECT jsonb_array_elements_text(out_addresses)),
+        ARRAY(SELECT jsonb_array_elements_text(tx_ids)),
+        in_coins, out_coins, metadata
+    FROM UNNEST(
+        $1::BIGINT[], $2::BIGINT[], $3::TEXT[], $4::TEXT[], $5::TEXT[], $6::JSONB[],
+        $7::JSONB[], $8::JSONB[], $9::JSONB[], $10::JSONB[], $11::JSONB[], $12::JSONB[]
+    ) AS page (
+        date, height, type, status, txid, pools, in_addresses, out_addresses, tx_ids,
+        in_coins, out_coins, metadata
+    )
+    ON CONFLICT (date, type, txid, in_hash) DO UPDATE
+    SET height = EXCLUDED.height,
+        status = EXCLUDED.status,
+        pools = EXCLUDED.pools,
+        in_addresses = EXCLUDED.in_addresses,
+        out_addresses = EXCLUDED.out_addresses,
+        tx_ids = EXCLUDED.tx_ids,
+        in_coins = EXCLUDED.in_coins,
+        out_coins = EXCLUDED.out_coins,
+        metadata = EXCLUDED.metadata
+    WHERE actions.status <> 'success' OR $13
+    RETURNING (xmax = 0)",
+    )
+    .bind(collect_column(actions, |a| a.date))
+    .bind(collect_column(actions, |a| a.height))
+    .bind(collect_column(actions, |a| a.action_type.clone()))
+    .bind(collect_column(actions, |a| a.status.clone()))
+    .bind(collect_column(actions, |a| a.tx_id().to_string()))
+    .bind(collect_column(actions, |a| json!(a.pools)))
+    .bind(collect_column(actions, |a| {
+        non_empty(a.inbound.iter().map(|t| t.address.clone()))
+    }))
+    .bind(collect_column(actions, |a| {
+        non_empty(a.outbound.iter().map(|t| t.address.clone()))
+    }))
+    .bind(collect_column(actions, |a| {
+        non_empty(a.inbound.iter().chain(&a.outbound).map(|t| t.tx_id.clone()))
+    }))
+    .bind(collect_column(actions, |a| transfers_json(&a.inbound)))
+    .bind(collect_column(actions, |a| transfers_json(&a.outbound)))
+    .bind(collect_column(actions, |a| a.metadata.clone()))
+    .bind(replay)
+    .fetch_all(conn)
+    .await
+}
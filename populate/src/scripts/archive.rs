@@ -0,0 +1,134 @@
+use super::dead_letters::{replay_dead_letters, resolve_dead_letters};
+use super::error::IngestError;
+use super::paging::PageWrite;
+use super::runs::RunStats;
+use super::utils::collect_column;
+use midgard::{History, Interval};
+use serde_json::Value;
+use sqlx::{PgConnection, PgPool};
+
+const BATCH_SIZE: i64 = 50;
+
+/// A page read back from `raw_midgard_pages`.
+#[derive(sqlx::FromRow)]
+pub struct ArchivedPage {
+    pub id: i64,
+    pub pool: String,
+    pub url: String,
+    pub body: Value,
+    pub fetched_at: i64, // UNIX timestamp (seconds), the `now` the page was ingested with
+}
+
+/// Stores a fetched page as returned by Midgard. Meant to run in the transaction that
+/// inserts the page's intervals.
+pub async fn archive_page<T>(
+    conn: &mut PgConnection,
+    dataset: &str,
+    pool_name: Option<&str>,
+    page: &History<T>,
+) -> Result<(), sqlx::Error> {
+    archive_body(conn, dataset, pool_name, &page.url, &page.raw).await
+}
+
+/// Stores a response body of an endpoint that is not paged into intervals, like actions.
+pub async fn archive_body(
+    conn: &mut PgConnection,
+    dataset: &str,
+    pool_name: Option<&str>,
+    url: &str,
+    body: &Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO raw_midgard_pages (dataset, pool, url, body) VALUES ($1, $2, $3, $4)")
+        .bind(dataset)
+        .bind(pool_name.unwrap_or(""))
+        .bind(url)
+        .bind(body)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// The next archived pages of `dataset` after `after_id`, oldest first.
+pub async fn archived_pages(
+    pool: &PgPool,
+    dataset: &str,
+    after_id: i64,
+) -> Result<Vec<ArchivedPage>, sqlx::Error> {
+    sqlx::query_as(
+        "
+    SELECT id, pool, url, body, EXTRACT(EPOCH FROM fetched_at)::BIGINT AS fetched_at
+    FROM raw_midgard_pages
+    WHERE dataset = $1 AND id > $2
+    ORDER BY id
+    LIMIT $3",
+    )
+    .bind(dataset)
+    .bind(after_id)
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .await
+}
+
+/// Marks the stored intervals starting at `start_times` provisional again, so replaying
+/// an archived page overwrites them the way the original ingestion did.
+pub async fn reopen_intervals(
+    conn: &mut PgConnection,
+    dataset: &str,
+    pool_name: Option<&str>,
+    start_times: &[i64],
+) -> Result<(), sqlx::Error> {
+    let pool_filter = if pool_name.is_some() {
+        "AND pool = $2"
+    } else {
+        ""
+    };
+    let sql = format!(
+        "UPDATE {} SET provisional = TRUE WHERE startTime = ANY($1) {}",
+        dataset, pool_filter
+    );
+    let mut query = sqlx::query(&sql).bind(start_times);
+    if let Some(name) = pool_name {
+        query = query.bind(name);
+    }
+    query.execute(conn).await?;
+    Ok(())
+}
+
+/// Rewrites the stored intervals of `dataset` from its archived pages without calling
+/// Midgard, replaying them in the order they were fetched. `insert_page` upserts a page the
+/// way the ingestion does, given the page's pool when `per_pool` and its fetch time.
+pub async fn reprocess<I, W>(
+    pool: &PgPool,
+    dataset: &str,
+    per_pool: bool,
+    stats: &mut RunStats,
+    insert_page: W,
+) -> Result<(), IngestError>
+where
+    I: Interval,
+    W: for<'c> Fn(&'c mut PgConnection, Option<&'c str>, &'c [I], i64) -> PageWrite<'c>,
+{
+    let mut after_id = 0;
+    loop {
+        let pages = archived_pages(pool, dataset, after_id).await?;
+        let Some(last_page) = pages.last() else {
+            break;
+        };
+        after_id = last_page.id;
+
+        for archived in pages {
+            let pool_name = per_pool.then_some(archived.pool.as_str());
+            let page = History::<I>::from_value(archived.url.as_str(), archived.body)?;
+            let start_times = collect_column(&page.intervals, |i| i.start_time());
+            let mut tx = pool.begin().await?;
+            reopen_intervals(&mut tx, dataset, pool_name, &start_times).await?;
+            let written =
+                insert_page(&mut tx, pool_name, &page.intervals, archived.fetched_at).await?;
+            stats.add_page(page.intervals.len(), &written, page.rejected.len());
+            replay_dead_letters(&mut tx, dataset, pool_name, &page.url, &page.rejected).await?;
+            resolve_dead_letters(&mut tx, dataset, pool_name, &start_times).await?;
+            tx.commit().await?;
+        }
+    }
+    Ok(())
+}
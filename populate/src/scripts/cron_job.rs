@@ -1,21 +1,179 @@
-use crate::scripts::{depth_price_history, earnings_history, runepool_history, swaps_history};
-use std::sync::Arc;
-use tokio::time::{sleep, Duration};
-pub async fn start_cron_job(pool: Arc<sqlx::PgPool>) {
+use crate::scripts::dataset::Dataset;
+use crate::scripts::leader::{acquire_leadership, still_leader, while_leader, Leader};
+use crate::scripts::runs;
+use chrono::{DateTime, TimeDelta, Utc};
+use cron::Schedule;
+use midgard::MidgardClient;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use tokio::time::sleep;
+
+// How often the leader of a dataset checks for runs queued through the admin API.
+const QUEUE_POLL_INTERVAL: TimeDelta = TimeDelta::seconds(5);
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DatasetSchedule {
+    pub dataset: String,
+    pub schedule: String,
+    pub next_run: Option<i64>, // UNIX timestamp (seconds) of the next scheduled run
+    pub running: bool,
+    pub leader: bool, // Whether this instance holds the dataset's ingestion lock
+}
+
+/// Schedule of every dataset, shared between the cron tasks and whoever reports on them.
+#[derive(Clone, Default)]
+pub struct SchedulerState {
+    datasets: Arc<RwLock<HashMap<Dataset, DatasetSchedule>>>,
+}
+
+impl SchedulerState {
+    pub fn snapshot(&self) -> Vec<DatasetSchedule> {
+        let mut schedules: Vec<_> = self.datasets.read().unwrap().values().cloned().collect();
+        schedules.sort_by(|a, b| a.dataset.cmp(&b.dataset));
+        schedules
+    }
+
+    fn update(&self, dataset: Dataset, f: impl FnOnce(&mut DatasetSchedule)) {
+        let mut datasets = self.datasets.write().unwrap();
+        let entry = datasets.entry(dataset).or_insert_with(|| DatasetSchedule {
+            dataset: dataset.to_string(),
+            schedule: String::new(),
+            next_run: None,
+            running: false,
+            leader: false,
+        });
+        f(entry);
+    }
+}
+
+fn parse_schedule(dataset: Dataset) -> Schedule {
+    let expression = dataset.schedule();
+    Schedule::from_str(&expression).unwrap_or_else(|err| {
+        eprintln!(
+            "Invalid schedule {:?} for {}: {}, using {:?}",
+            expression,
+            dataset,
+            err,
+            dataset.default_schedule()
+        );
+        Schedule::from_str(dataset.default_schedule()).expect("default schedules are valid")
+    })
+}
+
+/// Runs every dataset in its own task on its own cron schedule. Only the instance holding a
+/// dataset's advisory lock ingests it; the others stand by and take over when it stops.
+/// A new leader runs once right away to catch up, and a run is never started while the
+/// previous one is in progress.
+/// After a successful run the dataset is scanned for gaps, which are re-fetched. Every
+/// run is recorded in `ingestion_runs`.
+pub async fn start_cron_job(pool: Arc<sqlx::PgPool>, client: MidgardClient, state: SchedulerState) {
     println!("Starting cron job");
+    let tasks: Vec<_> = Dataset::ALL
+        .into_iter()
+        .map(|dataset| {
+            tokio::spawn(run_dataset(
+                dataset,
+                pool.clone(),
+                client.clone(),
+                state.clone(),
+            ))
+        })
+        .collect();
+    for task in tasks {
+        if let Err(err) = task.await {
+            eprintln!("Cron task failed: {}", err);
+        }
+    }
+}
+
+async fn run_dataset(
+    dataset: Dataset,
+    pool: Arc<sqlx::PgPool>,
+    client: MidgardClient,
+    state: SchedulerState,
+) {
+    let schedule = parse_schedule(dataset);
+    state.update(dataset, |s| s.schedule = schedule.to_string());
     loop {
-        if let Err(err) = depth_price_history::fetch_and_insert_data(&*pool).await {
-            eprintln!("Error running depth_price_history: {}", err);
+        let mut leader = acquire_leadership(&pool, dataset).await;
+        state.update(dataset, |s| s.leader = true);
+        let lost = lead_dataset(dataset, &pool, &client, &state, &schedule, &mut leader).await;
+        if !lost {
+            return;
         }
-        if let Err(err) = earnings_history::fetch_and_insert_data(&*pool).await {
-            eprintln!("Error running earnings_history: {}", err);
+        state.update(dataset, |s| {
+            s.leader = false;
+            s.next_run = None;
+        });
+        eprintln!("Lost the {} lock, standing by", dataset);
+    }
+}
+
+/// Runs `dataset` on its schedule for as long as the lock session stays alive, stopping a
+/// run in progress when it dies. Returns `true` once the lock is lost and `false` if the schedule has no upcoming run.
+async fn lead_dataset(
+    dataset: Dataset,
+    pool: &sqlx::PgPool,
+    client: &MidgardClient,
+    state: &SchedulerState,
+    schedule: &Schedule,
+    leader: &mut Leader,
+) -> bool {
+    loop {
+        if !still_leader(&mut leader.conn).await {
+            return true;
         }
-        if let Err(err) = runepool_history::fetch_and_insert_data(&*pool).await {
-            eprintln!("Error running runepool_history: {}", err);
+        state.update(dataset, |s| s.running = true);
+        println!("Running {}", dataset);
+        let run = runs::sync(pool, client, dataset, Some(leader.pid));
+        let result = while_leader(&mut leader.conn, run).await;
+        state.update(dataset, |s| s.running = false);
+        match result {
+            Some(Err(err)) => eprintln!("Error running {}: {}", dataset, err),
+            Some(Ok(_)) => {}
+            None => return true,
         }
-        if let Err(err) = swaps_history::fetch_and_insert_data(&*pool).await {
-            eprintln!("Error running swaps_history: {}", err);
+
+        // Slots missed while the run was in progress are skipped, not queued.
+        let Some(next_run) = schedule.upcoming(Utc).next() else {
+            eprintln!("No upcoming run for {}, stopping", dataset);
+            return false;
+        };
+        state.update(dataset, |s| s.next_run = Some(next_run.timestamp()));
+
+        // Runs queued through the admin API are picked up while waiting for the next slot.
+        while Utc::now() < next_run {
+            if !still_leader(&mut leader.conn).await {
+                return true;
+            }
+            match runs::claim_queued(pool, dataset, leader.pid).await {
+                Ok(Some(run)) => {
+                    let id = run.id;
+                    state.update(dataset, |s| s.running = true);
+                    let run = runs::run_queued(pool, client, dataset, run);
+                    let result = while_leader(&mut leader.conn, run).await;
+                    state.update(dataset, |s| s.running = false);
+                    match result {
+                        Some(Err(err)) => {
+                            eprintln!("Queued run {} of {} failed: {}", id, dataset, err)
+                        }
+                        Some(Ok(_)) => {}
+                        None => return true,
+                    }
+                    continue;
+                }
+                Ok(None) => {}
+                Err(err) => eprintln!("Error claiming queued {} runs: {}", dataset, err),
+            }
+            sleep_until(next_run.min(Utc::now() + QUEUE_POLL_INTERVAL)).await;
         }
-        sleep(Duration::from_secs(3600)).await;
+    }
+}
+
+async fn sleep_until(time: DateTime<Utc>) {
+    if let Ok(wait) = (time - Utc::now()).to_std() {
+        sleep(wait).await;
     }
 }
@@ -0,0 +1,246 @@
+use super::error::IngestError;
+use super::runs::RunStats;
+use super::utils::get_pools;
+use super::{
+    actions, depth_price_history, earnings_history, liquidity_changes_history, network_snapshots,
+    pools, runepool_history, savers_history, swaps_history, tvl_history,
+};
+use midgard::{MidgardClient, Transport};
+use sqlx::PgPool;
+use std::fmt;
+use std::str::FromStr;
+
+/// A Midgard history endpoint mirrored into its own table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dataset {
+    Actions,
+    DepthPriceHistory,
+    EarningHistory,
+    LiquidityChangesHistory,
+    NetworkSnapshots,
+    Pools,
+    RunepoolHistory,
+    SaversHistory,
+    SwapHistory,
+    TvlHistory,
+}
+
+impl Dataset {
+    pub const ALL: [Dataset; 10] = [
+        Dataset::Actions,
+        Dataset::DepthPriceHistory,
+        Dataset::EarningHistory,
+        Dataset::LiquidityChangesHistory,
+        Dataset::NetworkSnapshots,
+        Dataset::Pools,
+        Dataset::RunepoolHistory,
+        Dataset::SaversHistory,
+        Dataset::SwapHistory,
+        Dataset::TvlHistory,
+    ];
+
+    /// The table the dataset is written to, also used as its key in `ingestion_state`.
+    pub fn table(&self) -> &'static str {
+        match self {
+            Dataset::Actions => "actions",
+            Dataset::DepthPriceHistory => "depth_price_history",
+            Dataset::EarningHistory => "earning_history",
+            Dataset::LiquidityChangesHistory => "liquidity_changes_history",
+            Dataset::NetworkSnapshots => "network_snapshots",
+            Dataset::Pools => "pools",
+            Dataset::RunepoolHistory => "runepool_history",
+            Dataset::SaversHistory => "savers_history",
+            Dataset::SwapHistory => "swap_history",
+            Dataset::TvlHistory => "tvl_history",
+        }
+    }
+
+    /// Whether the dataset is ingested separately for every pool.
+    pub fn is_per_pool(&self) -> bool {
+        matches!(
+            self,
+            Dataset::DepthPriceHistory | Dataset::LiquidityChangesHistory | Dataset::SaversHistory
+        )
+    }
+
+    /// Whether the dataset mirrors a history endpoint. Snapshot datasets store Midgard's
+    /// current state instead, so there is nothing to backfill, replay or scan for gaps.
+    pub fn has_history(&self) -> bool {
+        !matches!(self, Dataset::NetworkSnapshots | Dataset::Pools)
+    }
+
+    /// Whether the dataset is stored as hourly intervals, which can be scanned for gaps.
+    pub fn has_intervals(&self) -> bool {
+        !matches!(
+            self,
+            Dataset::Actions | Dataset::NetworkSnapshots | Dataset::Pools
+        )
+    }
+
+    /// Cron expression (with seconds) used when `SCHEDULE_<TABLE>` is not set.
+    pub fn default_schedule(&self) -> &'static str {
+        match self {
+            Dataset::Actions | Dataset::SwapHistory => "0 */5 * * * *",
+            Dataset::NetworkSnapshots | Dataset::Pools => "0 */15 * * * *",
+            _ => "0 0 * * * *",
+        }
+    }
+
+    /// The cron expression configured for this dataset, e.g. `SCHEDULE_SWAP_HISTORY`.
+    pub fn schedule(&self) -> String {
+        let var = format!("SCHEDULE_{}", self.table().to_uppercase());
+        std::env::var(var).unwrap_or_else(|_| self.default_schedule().to_string())
+    }
+
+    pub async fn fetch_and_insert_data<T: Transport>(
+        &self,
+        pool: &PgPool,
+        client: &MidgardClient<T>,
+        stats: &mut RunStats,
+    ) -> Result<(), IngestError> {
+        match self {
+            Dataset::Actions => actions::fetch_and_insert_data(pool, client, stats).await,
+            Dataset::DepthPriceHistory => {
+                depth_price_history::fetch_and_insert_data(pool, client, stats).await
+            }
+            Dataset::EarningHistory => {
+                earnings_history::fetch_and_insert_data(pool, client, stats).await
+            }
+            Dataset::LiquidityChangesHistory => {
+                liquidity_changes_history::fetch_and_insert_data(pool, client, stats).await
+            }
+            Dataset::NetworkSnapshots => network_snapshots::snapshot(pool, client, stats).await,
+            Dataset::Pools => pools::snapshot(pool, client, stats).await,
+            Dataset::RunepoolHistory => {
+                runepool_history::fetch_and_insert_data(pool, client, stats).await
+            }
+            Dataset::SaversHistory => {
+                savers_history::fetch_and_insert_data(pool, client, stats).await
+            }
+            Dataset::SwapHistory => swaps_history::fetch_and_insert_data(pool, client, stats).await,
+            Dataset::TvlHistory => tvl_history::fetch_and_insert_data(pool, client, stats).await,
+        }
+    }
+
+    /// Rewrites the stored intervals from `raw_midgard_pages` without calling Midgard.
+    pub async fn reprocess(&self, pool: &PgPool, stats: &mut RunStats) -> Result<(), IngestError> {
+        match self {
+            Dataset::Actions => actions::reprocess(pool, stats).await,
+            Dataset::DepthPriceHistory => depth_price_history::reprocess(pool, stats).await,
+            Dataset::EarningHistory => earnings_history::reprocess(pool, stats).await,
+            Dataset::LiquidityChangesHistory => {
+                liquidity_changes_history::reprocess(pool, stats).await
+            }
+            Dataset::NetworkSnapshots | Dataset::Pools => {
+                Err(IngestError::NoHistory(self.to_string()))
+            }
+            Dataset::RunepoolHistory => runepool_history::reprocess(pool, stats).await,
+            Dataset::SaversHistory => savers_history::reprocess(pool, stats).await,
+            Dataset::SwapHistory => swaps_history::reprocess(pool, stats).await,
+            Dataset::TvlHistory => tvl_history::reprocess(pool, stats).await,
+        }
+    }
+
+    /// Re-fetches the intervals starting in `[from, to)` without touching the checkpoint.
+    /// Per-pool datasets cover `pool_name`, or every pool when it is `None`.
+    pub async fn backfill<T: Transport>(
+        &self,
+        pool: &PgPool,
+        client: &MidgardClient<T>,
+        pool_name: Option<&str>,
+        from: i64,
+        to: i64,
+        stats: &mut RunStats,
+    ) -> Result<(), IngestError> {
+        match self {
+            Dataset::Actions => {
+                actions::fetch_and_insert_range(pool, client, from, to, false, stats).await
+            }
+            Dataset::DepthPriceHistory => {
+                let pool_names = match pool_name {
+                    Some(name) => vec![name.to_string()],
+                    None => get_pools(client).await?,
+                };
+                for name in pool_names {
+                    depth_price_history::fetch_and_insert_range(
+                        pool, client, &name, from, to, false, stats,
+                    )
+                    .await?;
+                }
+                Ok(())
+            }
+            Dataset::EarningHistory => {
+                earnings_history::fetch_and_insert_range(pool, client, from, to, false, stats).await
+            }
+            Dataset::LiquidityChangesHistory => {
+                let pool_names = match pool_name {
+                    Some(name) => vec![name.to_string()],
+                    None => liquidity_changes_history::series(client).await?,
+                };
+                for name in pool_names {
+                    liquidity_changes_history::fetch_and_insert_range(
+                        pool, client, &name, from, to, false, stats,
+                    )
+                    .await?;
+                }
+                Ok(())
+            }
+            Dataset::NetworkSnapshots | Dataset::Pools => {
+                Err(IngestError::NoHistory(self.to_string()))
+            }
+            Dataset::RunepoolHistory => {
+                runepool_history::fetch_and_insert_range(pool, client, from, to, false, stats).await
+            }
+            Dataset::SaversHistory => {
+                let pool_names = match pool_name {
+                    Some(name) => vec![name.to_string()],
+                    None => get_pools(client).await?,
+                };
+                for name in pool_names {
+                    savers_history::fetch_and_insert_range(
+                        pool, client, &name, from, to, false, stats,
+                    )
+                    .await?;
+                }
+                Ok(())
+            }
+            Dataset::SwapHistory => {
+                swaps_history::fetch_and_insert_range(pool, client, from, to, false, stats).await
+            }
+            Dataset::TvlHistory => {
+                tvl_history::fetch_and_insert_range(pool, client, from, to, false, stats).await
+            }
+        }
+    }
+}
+
+impl fmt::Display for Dataset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.table())
+    }
+}
+
+/// Accepts the table name (`swap_history`) as well as short names (`swaps`, `depths`).
+impl FromStr for Dataset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "actions" => Ok(Dataset::Actions),
+            "depth_price_history" | "depth_history" | "depths" | "depth" => {
+                Ok(Dataset::DepthPriceHistory)
+            }
+            "earning_history" | "earnings_history" | "earnings" => Ok(Dataset::EarningHistory),
+            "liquidity_changes_history" | "liquidity_changes" => {
+                Ok(Dataset::LiquidityChangesHistory)
+            }
+            "network_snapshots" | "network" => Ok(Dataset::NetworkSnapshots),
+            "pools" | "pool_snapshots" => Ok(Dataset::Pools),
+            "runepool_history" | "runepool" => Ok(Dataset::RunepoolHistory),
+            "savers_history" | "savers" => Ok(Dataset::SaversHistory),
+            "swap_history" | "swaps_history" | "swaps" => Ok(Dataset::SwapHistory),
+            "tvl_history" | "tvl" => Ok(Dataset::TvlHistory),
+            other => Err(format!("Unknown dataset `{}`", other)),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use super::dataset::Dataset;
+use super::error::IngestError;
+use super::runs::{self, RunKind};
+use super::utils::collect_column;
+use midgard::RejectedInterval;
+use sqlx::{PgConnection, PgPool};
+use std::str::FromStr;
+
+/// Records the intervals of a page that were rejected. Meant to run in the transaction
+/// that inserts the rest of the page.
+pub async fn save_dead_letters(
+    conn: &mut PgConnection,
+    dataset: &str,
+    pool_name: Option<&str>,
+    url: &str,
+    rejected: &[RejectedInterval],
+) -> Result<(), sqlx::Error> {
+    for interval in rejected {
+        eprintln!(
+            "Rejected {} {:?} interval {:?}: {}",
+            dataset, pool_name, interval.start_time, interval.error
+        );
+    }
+    upsert_dead_letters(conn, dataset, pool_name, url, rejected, true).await
+}
+
+/// Records the intervals rejected when an archived page is replayed. They were not
+/// fetched again, so the ones already recorded only get their error updated.
+pub async fn replay_dead_letters(
+    conn: &mut PgConnection,
+    dataset: &str,
+    pool_name: Option<&str>,
+    url: &str,
+    rejected: &[RejectedInterval],
+) -> Result<(), sqlx::Error> {
+    upsert_dead_letters(conn, dataset, pool_name, url, rejected, false).await
+}
+
+async fn upsert_dead_letters(
+    conn: &mut PgConnection,
+    dataset: &str,
+    pool_name: Option<&str>,
+    url: &str,
+    rejected: &[RejectedInterval],
+    fetched: bool,
+) -> Result<(), sqlx::Error> {
+    if rejected.is_empty() {
+        return Ok(());
+    }
+    sqlx::query(
+        "
+    INSERT INTO ingestion_dead_letters (dataset, pool, start_time, end_time, url, payload, error)
+    SELECT $1, $2, * FROM UNNEST($3::BIGINT[], $4::BIGINT[], $5::TEXT[], $6::JSONB[], $7::TEXT[])
+    ON CONFLICT (dataset, pool, md5(payload::text)) DO UPDATE
+    SET error = EXCLUDED.error,
+        url = CASE WHEN $8 THEN EXCLUDED.url ELSE ingestion_dead_letters.url END,
+        attempts = ingestion_dead_letters.attempts + CASE WHEN $8 THEN 1 ELSE 0 END,
+        last_seen_at = CASE WHEN $8 THEN NOW() ELSE ingestion_dead_letters.last_seen_at END,
+        resolved_at = CASE WHEN $8 THEN NULL ELSE ingestion_dead_letters.resolved_at END",
+    )
+    .bind(dataset)
+    .bind(pool_name.unwrap_or(""))
+    .bind(collect_column(rejected, |r| r.start_time))
+    .bind(collect_column(rejected, |r| r.end_time))
+    .bind(collect_column(rejected, |_| url.to_string()))
+    .bind(collect_column(rejected, |r| r.raw.clone()))
+    .bind(collect_column(rejected, |r| r.error.to_string()))
+    .bind(fetched)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Marks the dead letters of intervals that have now been ingested as resolved.
+pub async fn resolve_dead_letters(
+    conn: &mut PgConnection,
+    dataset: &str,
+    pool_name: Option<&str>,
+    start_times: &[i64],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "
+    UPDATE ingestion_dead_letters SET resolved_at = NOW()
+    WHERE dataset = $1 AND pool = $2 AND start_time = ANY($3) AND resolved_at IS NULL",
+    )
+    .bind(dataset)
+    .bind(pool_name.unwrap_or(""))
+    .bind(start_times)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Queues a run re-fetching the interval of dead letter `id`, so the retry goes through
+/// the dataset's leader and its Midgard request budget. Returns the run id. Whether the
+/// interval was ingested shows in the row's `resolved_at` once the run is done; if it is
+/// rejected again its `attempts` go up.
+pub async fn retry_dead_letter(pool: &PgPool, id: i64) -> Result<i64, IngestError> {
+    let row: Option<(String, String, Option<i64>, Option<i64>)> = sqlx::query_as(
+        "SELECT dataset, pool, start_time, end_time FROM ingestion_dead_letters WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    let Some((dataset, pool_name, start_time, end_time)) = row else {
+        return Err(IngestError::DeadLetterNotFound(id));
+    };
+    let (Some(start_time), Some(end_time)) = (start_time, end_time) else {
+        return Err(IngestError::NotRetryable(id));
+    };
+    let dataset = Dataset::from_str(&dataset).map_err(|_| IngestError::NotRetryable(id))?;
+    // Per-pool datasets may keep a series under the empty pool name, e.g. network totals.
+    let pool_name =
+        Some(pool_name.as_str()).filter(|name| dataset.is_per_pool() || !name.is_empty());
+
+    let range = Some((start_time, end_time));
+    let run_id = runs::enqueue_run(pool, dataset, RunKind::Retry, pool_name, range).await?;
+    println!("Queued run {} retrying dead letter {}", run_id, id);
+    Ok(run_id)
+}
@@ -1,118 +1,147 @@
-use super::utils::get_last_successful_entry_for_table;
-use crate::models::depth_price_history::DepthPriceHistory;
-use bigdecimal::BigDecimal;
+use super::archive;
+use super::error::IngestError;
+use super::paging::{ingest_range, Series};
+use super::runs::RunStats;
+use super::utils::{collect_column, get_checkpoint, get_pools, is_provisional, to_decimal};
 use chrono::Utc;
-use reqwest::get;
-use serde_json::Value;
-use sqlx::Error;
-use std::str::FromStr;
+use midgard::{DepthHistoryInterval, MidgardClient, Transport};
+use sqlx::{PgConnection, PgPool};
 
-pub async fn fetch_and_insert_data(pool: &sqlx::PgPool) -> Result<(), Error> {
-    let mut from_time = get_last_successful_entry_for_table(&pool, "depth_price_history").await;
-    let end_time = Utc::now().timestamp();
-    loop {
-        let url = format!(
-            "https://midgard.ninerealms.com/v2/history/depths/BTC.BTC?interval=hour&from={}&count=400", 
-            from_time
-        );
-        println!("{:?}", url);
-        let response: Value = get(&url).await.unwrap().json().await.unwrap();
-        if let Some(intervals) = response["intervals"].as_array() {
-            for entry in intervals {
-                let asset_price_history = DepthPriceHistory {
-                    assetDepth: entry["assetDepth"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    assetPrice: entry["assetPrice"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    assetPriceUSD: entry["assetPriceUSD"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    liquidityUnits: entry["liquidityUnits"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    luvi: entry["luvi"].as_str().unwrap().parse::<f64>().unwrap(),
-                    membersCount: entry["membersCount"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i32>()
-                        .unwrap(),
-                    runeDepth: entry["runeDepth"].as_str().unwrap().parse::<i64>().unwrap(),
-                    synthSupply: entry["synthSupply"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    synthUnits: entry["synthUnits"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    units: entry["units"].as_str().unwrap().parse::<i64>().unwrap(),
-                    startTime: entry["startTime"].as_str().unwrap().parse::<i64>().unwrap(),
-                    endTime: entry["endTime"].as_str().unwrap().parse::<i64>().unwrap(),
-                    id: None,
-                };
-                match sqlx::query!(
-                    r#"
-                    INSERT INTO Depth_Price_History (
-                        assetDepth, assetPrice, assetPriceUSD, liquidityUnits, luvi,
-                        membersCount, runeDepth, synthSupply, synthUnits, units, startTime, endTime
-                    )
-                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
-                    "#,
-                    asset_price_history.assetDepth,
-                    BigDecimal::from_str(&asset_price_history.assetPrice.to_string()).unwrap(),
-                    BigDecimal::from_str(&asset_price_history.assetPriceUSD.to_string()).unwrap(),
-                    asset_price_history.liquidityUnits,
-                    BigDecimal::from_str(&asset_price_history.luvi.to_string()).unwrap(),
-                    asset_price_history.membersCount,
-                    asset_price_history.runeDepth,
-                    asset_price_history.synthSupply,
-                    asset_price_history.synthUnits,
-                    asset_price_history.units,
-                    asset_price_history.startTime,
-                    asset_price_history.endTime
-                )
-                .execute(pool)
-                .await
-                {
-                    Ok(_) => {}
-                    Err(e) => {
-                        if let sqlx::Error::Database(db_err) = &e {
-                            if db_err.code() == Some("23505".into()) {
-                                //duplicate entry
-                                continue;
-                            }
-                        }
-                        return Err(e);
-                    }
-                };
-            }
-            let last_entry = intervals.last().unwrap();
-            let last_end_time = last_entry["endTime"]
-                .as_str()
-                .unwrap()
-                .parse::<i64>()
-                .unwrap();
+const DATASET: &str = "depth_price_history";
 
-            if last_end_time >= end_time {
-                break;
-            }
-
-            from_time = last_end_time;
-        } else {
-            break;
+pub async fn fetch_and_insert_data<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    // A failing pool does not hold up the others, but fails the run once they are done.
+    let mut failures = Vec::new();
+    for pool_name in get_pools(client).await? {
+        if let Err(err) = fetch_and_insert_pool_data(pool, client, &pool_name, stats).await {
+            eprintln!(
+                "Error running depth_price_history for {}: {}",
+                pool_name, err
+            );
+            failures.push((pool_name, err));
         }
     }
-    Ok(())
+    IngestError::check_pools(failures)
+}
+
+async fn fetch_and_insert_pool_data<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    pool_name: &str,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let from_time = get_checkpoint(pool, DATASET, Some(pool_name)).await?;
+    let to = Utc::now().timestamp();
+    fetch_and_insert_range(pool, client, pool_name, from_time, to, true, stats).await
+}
+
+/// Ingests the depth and price intervals of `pool_name` starting in `[from, to)`, see
+/// [`ingest_range`].
+pub async fn fetch_and_insert_range<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    pool_name: &str,
+    from: i64,
+    to: i64,
+    save_progress: bool,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let series = Series {
+        dataset: DATASET,
+        pool: Some(pool_name),
+    };
+    ingest_range(
+        pool,
+        series,
+        (from, to),
+        save_progress,
+        stats,
+        |from_time, count| client.depth_history(pool_name, from_time, count),
+        |conn, pool_name, intervals, now| {
+            Box::pin(insert_page(
+                conn,
+                pool_name.unwrap_or_default(),
+                intervals,
+                now,
+            ))
+        },
+    )
+    .await
+}
+
+pub async fn reprocess(pool: &PgPool, stats: &mut RunStats) -> Result<(), IngestError> {
+    archive::reprocess(
+        pool,
+        DATASET,
+        true,
+        stats,
+        |conn, pool_name, intervals, now| {
+            Box::pin(insert_page(
+                conn,
+                pool_name.unwrap_or_default(),
+                intervals,
+                now,
+            ))
+        },
+    )
+    .await
+}
+
+/// Upserts a page of intervals; those ending within the hour of `now` stay provisional.
+/// Returns one flag per row written, `true` when it was inserted rather than updated.
+async fn insert_page(
+    conn: &mut PgConnection,
+    pool_name: &str,
+    intervals: &[DepthHistoryInterval],
+    now: i64,
+) -> Result<Vec<bool>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        INSERT INTO Depth_Price_History (
+            pool, assetDepth, assetPrice, assetPriceUSD, liquidityUnits, luvi,
+            membersCount, runeDepth, synthSupply, synthUnits, units, startTime, endTime,
+            provisional
+        )
+        SELECT $1, * FROM UNNEST(
+            $2::BIGINT[], $3::NUMERIC[], $4::NUMERIC[], $5::BIGINT[], $6::NUMERIC[],
+            $7::INT[], $8::BIGINT[], $9::BIGINT[], $10::BIGINT[], $11::BIGINT[],
+            $12::BIGINT[], $13::BIGINT[], $14::BOOL[]
+        )
+        ON CONFLICT (pool, startTime, endTime) DO UPDATE
+        SET assetDepth = EXCLUDED.assetDepth,
+            assetPrice = EXCLUDED.assetPrice,
+            assetPriceUSD = EXCLUDED.assetPriceUSD,
+            liquidityUnits = EXCLUDED.liquidityUnits,
+            luvi = EXCLUDED.luvi,
+            membersCount = EXCLUDED.membersCount,
+            runeDepth = EXCLUDED.runeDepth,
+            synthSupply = EXCLUDED.synthSupply,
+            synthUnits = EXCLUDED.synthUnits,
+            units = EXCLUDED.units,
+            provisional = EXCLUDED.provisional
+        WHERE Depth_Price_History.provisional
+        RETURNING (xmax = 0) AS "inserted!"
+        "#,
+        pool_name,
+        &collect_column(intervals, |e| e.asset_depth),
+        &collect_column(intervals, |e| to_decimal(e.asset_price)),
+        &collect_column(intervals, |e| to_decimal(e.asset_price_usd)),
+        &collect_column(intervals, |e| e.liquidity_units),
+        &collect_column(intervals, |e| to_decimal(e.luvi)),
+        &collect_column(intervals, |e| e.members_count),
+        &collect_column(intervals, |e| e.rune_depth),
+        &collect_column(intervals, |e| e.synth_supply),
+        &collect_column(intervals, |e| e.synth_units),
+        &collect_column(intervals, |e| e.units),
+        &collect_column(intervals, |e| e.start_time),
+        &collect_column(intervals, |e| e.end_time),
+        &collect_column(intervals, |e| is_provisional(e.end_time, now)),
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.inserted).collect())
 }
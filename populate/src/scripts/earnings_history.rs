@@ -1,108 +1,159 @@
-use super::utils::get_last_successful_entry_for_table;
-use crate::models::earnings_history::{EarningHistoryData, EarningHistoryNestedData};
+use super::archive;
+use super::error::IngestError;
+use super::paging::{ingest_range, Series};
+use super::runs::RunStats;
+use super::utils::{collect_column, get_checkpoint, is_provisional, to_decimal};
 use chrono::Utc;
-use reqwest::get;
-use serde_json::Value;
-use sqlx::{Error, PgPool};
+use midgard::{EarningsHistoryInterval, EarningsHistoryPool, MidgardClient, Transport};
+use sqlx::{Error, PgConnection, PgPool};
 
-pub async fn fetch_and_insert_data(pool: &PgPool) -> Result<(), Error> {
-    let mut from_time = get_last_successful_entry_for_table(pool, "earning_history").await;
-    println!("{:?}", from_time);
-    let end_time = Utc::now().timestamp();
+const DATASET: &str = "earning_history";
 
-    loop {
-        let url = format!(
-            "https://midgard.ninerealms.com/v2/history/earnings?interval=hour&from={}&count=400",
-            from_time
-        );
-        println!("{:?}", url);
-
-        let response: Value = get(&url).await.unwrap().json().await.unwrap();
-
-        for interval in response["intervals"].as_array().unwrap() {
-            let mut nested_ids = Vec::new();
-            for nested in interval["pools"].as_array().unwrap() {
-                let nested_data: EarningHistoryNestedData =
-                    serde_json::from_value(nested.clone()).unwrap();
-                let nested_id = insert_pool(pool, &nested_data).await?;
-                nested_ids.push(nested_id);
-            }
+pub async fn fetch_and_insert_data<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let from_time = get_checkpoint(pool, DATASET, None).await?;
+    fetch_and_insert_range(pool, client, from_time, Utc::now().timestamp(), true, stats).await
+}
 
-            let interval_data = EarningHistoryData {
-                avgNodeCount: interval["avgNodeCount"].as_str().unwrap().parse().unwrap(),
-                blockRewards: interval["blockRewards"].as_str().unwrap().parse().unwrap(),
-                bondingEarnings: interval["bondingEarnings"]
-                    .as_str()
-                    .unwrap()
-                    .parse()
-                    .unwrap(),
-                earnings: interval["earnings"].as_str().unwrap().parse().unwrap(),
-                endTime: interval["endTime"].as_str().unwrap().parse().unwrap(),
-                liquidityEarnings: interval["liquidityEarnings"]
-                    .as_str()
-                    .unwrap()
-                    .parse()
-                    .unwrap(),
-                liquidityFees: interval["liquidityFees"].as_str().unwrap().parse().unwrap(),
-                runePriceUsd: interval["runePriceUSD"].as_str().unwrap().parse().unwrap(),
-                startTime: interval["startTime"].as_str().unwrap().parse().unwrap(),
-                pool: nested_ids,
-            };
-            insert_earning_interval(pool, &interval_data).await?;
-        }
+/// Ingests the earnings intervals starting in `[from, to)`, see [`ingest_range`].
+pub async fn fetch_and_insert_range<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    from: i64,
+    to: i64,
+    save_progress: bool,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let series = Series {
+        dataset: DATASET,
+        pool: None,
+    };
+    ingest_range(
+        pool,
+        series,
+        (from, to),
+        save_progress,
+        stats,
+        |from_time, count| client.earnings_history(from_time, count),
+        |conn, _, intervals, now| Box::pin(insert_page(conn, intervals, now)),
+    )
+    .await
+}
 
-        let last_end_time = response["intervals"].as_array().unwrap().last().unwrap()["endTime"]
-            .as_str()
-            .unwrap()
-            .parse::<i64>()
-            .unwrap();
+pub async fn reprocess(pool: &PgPool, stats: &mut RunStats) -> Result<(), IngestError> {
+    archive::reprocess(pool, DATASET, false, stats, |conn, _, intervals, now| {
+        Box::pin(insert_page(conn, intervals, now))
+    })
+    .await
+}
 
-        if last_end_time >= end_time {
-            break;
+/// Upserts a page of intervals; those ending within the hour of `now` stay provisional.
+/// Returns one flag per interval written, `true` when it was inserted rather than updated.
+async fn insert_page(
+    conn: &mut PgConnection,
+    intervals: &[EarningsHistoryInterval],
+    now: i64,
+) -> Result<Vec<bool>, Error> {
+    let mut written = Vec::new();
+    for interval in intervals {
+        // A finalized interval that is already stored keeps its pool rows as they are.
+        let provisional = is_provisional(interval.end_time, now);
+        if let Some((earning_history_id, inserted)) =
+            upsert_earning_interval(&mut *conn, interval, provisional).await?
+        {
+            upsert_pools(&mut *conn, earning_history_id, &interval.pools).await?;
+            written.push(inserted);
         }
-
-        from_time = last_end_time;
     }
-
-    Ok(())
-}
-
-async fn insert_pool(pool: &PgPool, nested: &EarningHistoryNestedData) -> Result<i32, Error> {
-    let query = "
-    INSERT INTO earning_history_nested (pool, assetliquidityfees, earnings, rewards, runeliquidityfees, saverearning, totalliquidityfeesrune)
-    VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id";
-    let nested_id: (i32,) = sqlx::query_as(query)
-        .bind(&nested.pool)
-        .bind(nested.assetLiquidityFees)
-        .bind(nested.earnings)
-        .bind(nested.rewards)
-        .bind(nested.runeLiquidityFees)
-        .bind(nested.saverEarning)
-        .bind(nested.totalLiquidityFeesRune)
-        .fetch_one(pool)
-        .await?;
-    Ok(nested_id.0)
+    Ok(written)
 }
 
-async fn insert_earning_interval(
-    pool: &PgPool,
-    interval: &EarningHistoryData,
+async fn upsert_pools(
+    conn: &mut PgConnection,
+    earning_history_id: i32,
+    pools: &[EarningsHistoryPool],
 ) -> Result<(), Error> {
-    let query = "
-    INSERT INTO earning_history (avgNodeCount, blockRewards, bondingEarnings, earnings, endTime, liquidityEarnings, liquidityFees, runePriceUsd, startTime, pools)
-    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) ON CONFLICT (startTime, endTime) DO NOTHING RETURNING id";
-    let _: (i32,) = sqlx::query_as(query)
-        .bind(interval.avgNodeCount)
-        .bind(interval.blockRewards)
-        .bind(interval.bondingEarnings)
-        .bind(interval.earnings)
-        .bind(interval.endTime)
-        .bind(interval.liquidityEarnings)
-        .bind(interval.liquidityFees)
-        .bind(interval.runePriceUsd)
-        .bind(interval.startTime)
-        .bind(interval.pool.clone())
-        .fetch_one(pool)
-        .await?;
+    let pool_names = collect_column(pools, |p| p.pool.clone());
+    sqlx::query!(
+        "DELETE FROM earning_history_nested WHERE earning_history_id = $1 AND pool <> ALL($2)",
+        earning_history_id,
+        &pool_names,
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO earning_history_nested (
+            earning_history_id, pool, assetliquidityfees, earnings, rewards,
+            runeliquidityfees, saverearning, totalliquidityfeesrune
+        )
+        SELECT $1, * FROM UNNEST(
+            $2::TEXT[], $3::BIGINT[], $4::BIGINT[], $5::BIGINT[], $6::BIGINT[],
+            $7::BIGINT[], $8::BIGINT[]
+        )
+        ON CONFLICT (earning_history_id, pool) DO UPDATE
+        SET assetliquidityfees = EXCLUDED.assetliquidityfees,
+            earnings = EXCLUDED.earnings,
+            rewards = EXCLUDED.rewards,
+            runeliquidityfees = EXCLUDED.runeliquidityfees,
+            saverearning = EXCLUDED.saverearning,
+            totalliquidityfeesrune = EXCLUDED.totalliquidityfeesrune
+        "#,
+        earning_history_id,
+        &pool_names,
+        &collect_column(pools, |p| p.asset_liquidity_fees),
+        &collect_column(pools, |p| p.earnings),
+        &collect_column(pools, |p| p.rewards),
+        &collect_column(pools, |p| p.rune_liquidity_fees),
+        &collect_column(pools, |p| p.saver_earning),
+        &collect_column(pools, |p| p.total_liquidity_fees_rune),
+    )
+    .execute(&mut *conn)
+    .await?;
     Ok(())
 }
+
+/// Returns the id of the inserted or refreshed row and whether it was inserted, or `None`
+/// if the interval is already stored and finalized.
+async fn upsert_earning_interval(
+    conn: &mut PgConnection,
+    interval: &EarningsHistoryInterval,
+    provisional: bool,
+) -> Result<Option<(i32, bool)>, Error> {
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO earning_history (
+            avgNodeCount, blockRewards, bondingEarnings, earnings, endTime,
+            liquidityEarnings, liquidityFees, runePriceUsd, startTime, provisional
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        ON CONFLICT (startTime, endTime) DO UPDATE
+        SET avgNodeCount = EXCLUDED.avgNodeCount,
+            blockRewards = EXCLUDED.blockRewards,
+            bondingEarnings = EXCLUDED.bondingEarnings,
+            earnings = EXCLUDED.earnings,
+            liquidityEarnings = EXCLUDED.liquidityEarnings,
+            liquidityFees = EXCLUDED.liquidityFees,
+            runePriceUsd = EXCLUDED.runePriceUsd,
+            provisional = EXCLUDED.provisional
+        WHERE earning_history.provisional
+        RETURNING id, (xmax = 0) AS "inserted!"
+        "#,
+        to_decimal(interval.avg_node_count),
+        interval.block_rewards,
+        interval.bonding_earnings,
+        interval.earnings,
+        interval.end_time,
+        interval.liquidity_earnings,
+        interval.liquidity_fees,
+        interval.rune_price_usd,
+        interval.start_time,
+        provisional,
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    Ok(row.map(|row| (row.id, row.inserted)))
+}
@@ -0,0 +1,86 @@
+use midgard::MidgardError;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum IngestError {
+    Midgard(MidgardError),
+    Database(sqlx::Error),
+    /// The table a dataset is written to does not exist (migrations not run?).
+    MissingTable(String),
+    /// There is no dead letter with this id.
+    DeadLetterNotFound(i64),
+    /// The dead letter's interval times or dataset are unknown, so it cannot be re-fetched.
+    NotRetryable(i64),
+    /// The dataset only snapshots Midgard's current state, there is no history to re-fetch.
+    NoHistory(String),
+    /// Ingestion of these pools of a per-pool dataset failed; the other pools were stored.
+    Pools(Vec<(String, IngestError)>),
+    /// Another session holds the dataset's advisory lock, e.g. the scheduler's leader.
+    Locked(String),
+}
+
+impl IngestError {
+    /// Fails with the errors of the pools in `failures`, if there are any.
+    pub fn check_pools(failures: Vec<(String, IngestError)>) -> Result<(), IngestError> {
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(IngestError::Pools(failures))
+        }
+    }
+}
+
+impl fmt::Display for IngestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IngestError::Midgard(err) => write!(f, "{}", err),
+            IngestError::Database(err) => write!(f, "Database error: {}", err),
+            IngestError::MissingTable(table) => write!(f, "Table `{}` does not exist", table),
+            IngestError::DeadLetterNotFound(id) => write!(f, "Dead letter {} not found", id),
+            IngestError::NotRetryable(id) => {
+                write!(f, "Dead letter {} has no interval to re-fetch", id)
+            }
+            IngestError::NoHistory(dataset) => {
+                write!(
+                    f,
+                    "`{}` is a snapshot and has no history to re-fetch",
+                    dataset
+                )
+            }
+            IngestError::Pools(failures) => {
+                write!(f, "{} pools failed:", failures.len())?;
+                for (pool_name, err) in failures {
+                    write!(f, " {:?}: {};", pool_name, err)?;
+                }
+                Ok(())
+            }
+            IngestError::Locked(dataset) => write!(
+                f,
+                "`{}` is being ingested by another instance, try again once its run finishes",
+                dataset
+            ),
+        }
+    }
+}
+
+impl std::error::Error for IngestError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IngestError::Midgard(err) => Some(err),
+            IngestError::Database(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<MidgardError> for IngestError {
+    fn from(err: MidgardError) -> Self {
+        IngestError::Midgard(err)
+    }
+}
+
+impl From<sqlx::Error> for IngestError {
+    fn from(err: sqlx::Error) -> Self {
+        IngestError::Database(err)
+    }
+}
@@ -0,0 +1,192 @@
+use super::dataset::Dataset;
+use super::error::IngestError;
+use super::runs::RunStats;
+use midgard::{MidgardClient, Transport};
+use sqlx::PgPool;
+
+const HOUR: i64 = 3600;
+
+/// A run of consecutive hourly intervals missing from a dataset's table.
+#[derive(Debug, Clone)]
+pub struct Gap {
+    pub dataset: Dataset,
+    pub pool: Option<String>,
+    pub start_time: i64, // startTime of the first missing interval
+    pub end_time: i64,   // endTime of the last missing interval
+}
+
+/// Finds the hours in `[from, to)` without a row in the dataset's table, leaving out the
+/// intervals Midgard returned but that were dead-lettered and the hours it was already
+/// found to have no interval for. Defaults to the range between
+/// the first stored interval and the checkpoint, so hours that were never ingested yet are
+/// not reported. Per-pool datasets are scanned for every pool that has a checkpoint.
+pub async fn find_gaps(
+    pool: &PgPool,
+    dataset: Dataset,
+    from: Option<i64>,
+    to: Option<i64>,
+) -> Result<Vec<Gap>, IngestError> {
+    if !dataset.has_intervals() {
+        return Ok(Vec::new());
+    }
+    let pool_names: Vec<Option<String>> = if dataset.is_per_pool() {
+        sqlx::query_scalar("SELECT pool FROM ingestion_state WHERE dataset = $1 ORDER BY pool")
+            .bind(dataset.table())
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(Some)
+            .collect()
+    } else {
+        vec![None]
+    };
+
+    let mut gaps = Vec::new();
+    for pool_name in pool_names {
+        gaps.extend(find_pool_gaps(pool, dataset, pool_name, from, to).await?);
+    }
+    Ok(gaps)
+}
+
+/// Scans the dataset for gaps and re-fetches each one from Midgard with a range limited
+/// to the gap. Returns the gaps that are still missing afterwards, whose hours are recorded
+/// as empty so later scans do not re-fetch them again.
+pub async fn refetch_gaps<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    dataset: Dataset,
+    stats: &mut RunStats,
+) -> Result<Vec<Gap>, IngestError> {
+    let gaps = find_gaps(pool, dataset, None, None).await?;
+    if gaps.is_empty() {
+        return Ok(gaps);
+    }
+
+    println!("Re-fetching {} gaps in {}", gaps.len(), dataset);
+    for gap in &gaps {
+        dataset
+            .backfill(
+                pool,
+                client,
+                gap.pool.as_deref(),
+                gap.start_time,
+                gap.end_time,
+                stats,
+            )
+            .await?;
+    }
+
+    let remaining = find_gaps(pool, dataset, None, None).await?;
+    for gap in &remaining {
+        eprintln!(
+            "{} {:?} still missing {} to {}, recording it as empty",
+            dataset, gap.pool, gap.start_time, gap.end_time
+        );
+        record_empty_hours(pool, gap).await?;
+    }
+    Ok(remaining)
+}
+
+/// Records the hours of a gap that Midgard returned nothing for.
+async fn record_empty_hours(pool: &PgPool, gap: &Gap) -> Result<(), IngestError> {
+    sqlx::query(&format!(
+        "
+    INSERT INTO ingestion_empty_hours (dataset, pool, start_time)
+    SELECT $1, $2, s FROM generate_series($3::BIGINT, $4::BIGINT - 1, {HOUR}) s
+    ON CONFLICT DO NOTHING"
+    ))
+    .bind(gap.dataset.table())
+    .bind(gap.pool.as_deref().unwrap_or(""))
+    .bind(gap.start_time)
+    .bind(gap.end_time)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn find_pool_gaps(
+    pool: &PgPool,
+    dataset: Dataset,
+    pool_name: Option<String>,
+    from: Option<i64>,
+    to: Option<i64>,
+) -> Result<Vec<Gap>, IngestError> {
+    let pool_filter = if pool_name.is_some() {
+        "AND t.pool = $3"
+    } else {
+        ""
+    };
+
+    let from = match from {
+        Some(from) => Some(from),
+        None => {
+            let sql = format!(
+                "SELECT MIN(t.startTime) FROM {} t WHERE TRUE {}",
+                dataset.table(),
+                pool_filter.replace("$3", "$1")
+            );
+            let mut query = sqlx::query_scalar(&sql);
+            if let Some(name) = &pool_name {
+                query = query.bind(name);
+            }
+            query.fetch_one(pool).await?
+        }
+    };
+    let to = match to {
+        Some(to) => Some(to),
+        None => {
+            sqlx::query_scalar(
+                "SELECT last_end_time FROM ingestion_state WHERE dataset = $1 AND pool = $2",
+            )
+            .bind(dataset.table())
+            .bind(pool_name.as_deref().unwrap_or(""))
+            .fetch_optional(pool)
+            .await?
+        }
+    };
+    let (Some(from), Some(to)) = (from, to) else {
+        return Ok(Vec::new());
+    };
+
+    // Midgard hourly intervals start on the hour.
+    let from = from - from.rem_euclid(HOUR);
+    let sql = format!(
+        "
+    SELECT s FROM generate_series($1::BIGINT, $2::BIGINT - 1, {HOUR}) s
+    WHERE NOT EXISTS (SELECT 1 FROM {} t WHERE t.startTime = s {})
+    AND NOT EXISTS (
+        SELECT 1 FROM ingestion_dead_letters d
+        WHERE d.dataset = '{}' AND d.start_time = s AND d.resolved_at IS NULL {}
+    )
+    AND NOT EXISTS (
+        SELECT 1 FROM ingestion_empty_hours e
+        WHERE e.dataset = '{}' AND e.start_time = s {}
+    )
+    ORDER BY s",
+        dataset.table(),
+        pool_filter,
+        dataset.table(),
+        pool_filter.replace("t.pool", "d.pool"),
+        dataset.table(),
+        pool_filter.replace("t.pool", "e.pool")
+    );
+    let mut query = sqlx::query_scalar(&sql).bind(from).bind(to);
+    if let Some(name) = &pool_name {
+        query = query.bind(name);
+    }
+    let missing: Vec<i64> = query.fetch_all(pool).await?;
+
+    let mut gaps: Vec<Gap> = Vec::new();
+    for start_time in missing {
+        match gaps.last_mut() {
+            Some(gap) if gap.end_time == start_time => gap.end_time = start_time + HOUR,
+            _ => gaps.push(Gap {
+                dataset,
+                pool: pool_name.clone(),
+                start_time,
+                end_time: start_time + HOUR,
+            }),
+        }
+    }
+    Ok(gaps)
+}
@@ -0,0 +1,115 @@
+use super::dataset::Dataset;
+use super::error::IngestError;
+use sqlx::{PgConnection, PgPool};
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::sleep;
+
+// First key of the two-key advisory locks, so they don't collide with other users of the database.
+const LOCK_NAMESPACE: i32 = 0x6d69_6467;
+
+// How often a leader's run checks that the lock session is still alive. Shorter than the
+// default poll of the instances standing by, so a run stops before another takes over.
+const LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often an instance that is not the leader of a dataset checks whether the lock
+/// was released, from `LEADER_POLL_SECONDS` (default 30).
+fn poll_interval() -> Duration {
+    let seconds = std::env::var("LEADER_POLL_SECONDS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(30);
+    Duration::from_secs(seconds)
+}
+
+/// The session holding the advisory lock of a dataset.
+pub struct Leader {
+    pub conn: PgConnection,
+    pub pid: i32, // Backend pid of the session, recorded on the runs the leader starts
+}
+
+/// Waits until this instance holds the advisory lock of `dataset` and returns the session
+/// holding it. The connection is detached from the pool: the lock lives as long as it
+/// does, and is released by Postgres when the instance dies, letting another take over.
+pub async fn acquire_leadership(pool: &PgPool, dataset: Dataset) -> Leader {
+    let mut waiting = false;
+    loop {
+        match try_lock(pool, dataset).await {
+            Ok(Some(leader)) => {
+                println!("Leading ingestion of {}", dataset);
+                interrupt_stale_runs(pool, dataset, leader.pid).await;
+                return leader;
+            }
+            Ok(None) if !waiting => {
+                println!("Another instance leads {}, standing by", dataset);
+                waiting = true;
+            }
+            Ok(None) => {}
+            Err(err) => eprintln!("Error taking the {} lock: {}", dataset, err),
+        }
+        sleep(poll_interval()).await;
+    }
+}
+
+async fn try_lock(pool: &PgPool, dataset: Dataset) -> Result<Option<Leader>, sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+    let (locked, pid): (bool, i32) =
+        sqlx::query_as("SELECT pg_try_advisory_lock($1, hashtext($2)), pg_backend_pid()")
+            .bind(LOCK_NAMESPACE)
+            .bind(dataset.table())
+            .fetch_one(&mut *conn)
+            .await?;
+    Ok(locked.then(|| Leader {
+        conn: conn.detach(),
+        pid,
+    }))
+}
+
+/// Takes the advisory lock of `dataset` for a run started from the CLI, so that it does
+/// not overlap a run of the scheduler's leader. Fails when another session holds it. The
+/// lock is released when the returned session is dropped.
+pub async fn lock_dataset(pool: &PgPool, dataset: Dataset) -> Result<Leader, IngestError> {
+    try_lock(pool, dataset)
+        .await?
+        .ok_or_else(|| IngestError::Locked(dataset.table().to_string()))
+}
+
+/// Whether the session holding the lock is still alive, checked before every run.
+pub async fn still_leader(conn: &mut PgConnection) -> bool {
+    sqlx::query("SELECT 1").execute(conn).await.is_ok()
+}
+
+/// Drives `run` for as long as the lock session stays alive, checking it every
+/// [`LOCK_CHECK_INTERVAL`]. Returns `None` once the lock is lost: the run is dropped, which
+/// rolls back the page in progress, and the next leader marks the run interrupted.
+pub async fn while_leader<F: Future>(conn: &mut PgConnection, run: F) -> Option<F::Output> {
+    tokio::pin!(run);
+    loop {
+        tokio::select! {
+            output = &mut run => return Some(output),
+            _ = sleep(LOCK_CHECK_INTERVAL) => {
+                if !still_leader(conn).await {
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// Runs still marked running that a previous leader started were left behind when it
+/// died. Runs started from the CLI have no leader and are left alone.
+async fn interrupt_stale_runs(pool: &PgPool, dataset: Dataset, pid: i32) {
+    let result = sqlx::query(
+        "
+    UPDATE ingestion_runs
+    SET status = 'failed', error = 'Interrupted: the instance running it stopped', finished_at = NOW()
+    WHERE dataset = $1 AND status = 'running' AND leader_pid IS NOT NULL AND leader_pid <> $2",
+    )
+    .bind(dataset.table())
+    .bind(pid)
+    .execute(pool)
+    .await;
+    if let Err(err) = result {
+        eprintln!("Error closing interrupted runs of {}: {}", dataset, err);
+    }
+}
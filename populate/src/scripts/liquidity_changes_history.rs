@@ -0,0 +1,167 @@
+use super::archive;
+use super::error::IngestError;
+use super::paging::{ingest_range, Series};
+use super::runs::RunStats;
+use super::utils::{collect_column, get_checkpoint, get_pools, is_provisional};
+use chrono::Utc;
+use midgard::{LiquidityChangesInterval, MidgardClient, Transport};
+use sqlx::{PgConnection, PgPool};
+
+const DATASET: &str = "liquidity_changes_history";
+
+/// The `pool` the network-wide totals are stored under.
+pub const NETWORK: &str = "";
+
+/// The network-wide series followed by every pool's.
+pub async fn series<T: Transport>(client: &MidgardClient<T>) -> Result<Vec<String>, IngestError> {
+    let mut names = vec![NETWORK.to_string()];
+    names.extend(get_pools(client).await?);
+    Ok(names)
+}
+
+pub async fn fetch_and_insert_data<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    // A failing series does not hold up the others, but fails the run once they are done.
+    let mut failures = Vec::new();
+    for pool_name in series(client).await? {
+        if let Err(err) = fetch_and_insert_pool_data(pool, client, &pool_name, stats).await {
+            eprintln!(
+                "Error running liquidity_changes_history for {:?}: {}",
+                pool_name, err
+            );
+            failures.push((pool_name, err));
+        }
+    }
+    IngestError::check_pools(failures)
+}
+
+async fn fetch_and_insert_pool_data<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    pool_name: &str,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let from_time = get_checkpoint(pool, DATASET, Some(pool_name)).await?;
+    let to = Utc::now().timestamp();
+    fetch_and_insert_range(pool, client, pool_name, from_time, to, true, stats).await
+}
+
+/// Ingests the intervals of `pool_name` (`NETWORK` for the totals) starting in
+/// `[from, to)`, see [`ingest_range`].
+pub async fn fetch_and_insert_range<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    pool_name: &str,
+    from: i64,
+    to: i64,
+    save_progress: bool,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let series = Series {
+        dataset: DATASET,
+        pool: Some(pool_name),
+    };
+    let midgard_pool = Some(pool_name).filter(|name| *name != NETWORK);
+    ingest_range(
+        pool,
+        series,
+        (from, to),
+        save_progress,
+        stats,
+        |from_time, count| client.liquidity_changes_history(midgard_pool, from_time, count),
+        |conn, pool_name, intervals, now| {
+            Box::pin(insert_page(
+                conn,
+                pool_name.unwrap_or(NETWORK),
+                intervals,
+                now,
+            ))
+        },
+    )
+    .await
+}
+
+pub async fn reprocess(pool: &PgPool, stats: &mut RunStats) -> Result<(), IngestError> {
+    archive::reprocess(
+        pool,
+        DATASET,
+        true,
+        stats,
+        |conn, pool_name, intervals, now| {
+            Box::pin(insert_page(
+                conn,
+                pool_name.unwrap_or(NETWORK),
+                intervals,
+                now,
+            ))
+        },
+    )
+    .await
+}
+
+/// Upserts a page of intervals; those ending within the hour of `now` stay provisional.
+/// Returns one flag per row written, `true` when it was inserted rather than updated.
+async fn insert_page(
+    conn: &mut PgConnection,
+    pool_name: &str,
+    intervals: &[LiquidityChangesInterval],
+    now: i64,
+) -> Result<Vec<bool>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        INSERT INTO liquidity_changes_history (
+            pool, startTime, endTime, addAssetLiquidityVolume, addRuneLiquidityVolume,
+            addLiquidityVolume, addLiquidityVolumeUsd, addLiquidityCount,
+            removeAssetLiquidityVolume, removeRuneLiquidityVolume, removeLiquidityVolume,
+            removeLiquidityVolumeUsd, removeLiquidityCount, netChange, netChangeUsd,
+            runePriceUsd, provisional
+        )
+        SELECT $1, * FROM UNNEST(
+            $2::BIGINT[], $3::BIGINT[], $4::BIGINT[], $5::BIGINT[], $6::BIGINT[],
+            $7::FLOAT8[], $8::BIGINT[], $9::BIGINT[], $10::BIGINT[], $11::BIGINT[],
+            $12::FLOAT8[], $13::BIGINT[], $14::BIGINT[], $15::FLOAT8[], $16::FLOAT8[],
+            $17::BOOL[]
+        )
+        ON CONFLICT (pool, startTime, endTime) DO UPDATE
+        SET addAssetLiquidityVolume = EXCLUDED.addAssetLiquidityVolume,
+            addRuneLiquidityVolume = EXCLUDED.addRuneLiquidityVolume,
+            addLiquidityVolume = EXCLUDED.addLiquidityVolume,
+            addLiquidityVolumeUsd = EXCLUDED.addLiquidityVolumeUsd,
+            addLiquidityCount = EXCLUDED.addLiquidityCount,
+            removeAssetLiquidityVolume = EXCLUDED.removeAssetLiquidityVolume,
+            removeRuneLiquidityVolume = EXCLUDED.removeRuneLiquidityVolume,
+            removeLiquidityVolume = EXCLUDED.removeLiquidityVolume,
+            removeLiquidityVolumeUsd = EXCLUDED.removeLiquidityVolumeUsd,
+            removeLiquidityCount = EXCLUDED.removeLiquidityCount,
+            netChange = EXCLUDED.netChange,
+            netChangeUsd = EXCLUDED.netChangeUsd,
+            runePriceUsd = EXCLUDED.runePriceUsd,
+            provisional = EXCLUDED.provisional
+        WHERE liquidity_changes_history.provisional
+        RETURNING (xmax = 0) AS "inserted!"
+        "#,
+        pool_name,
+        &collect_column(intervals, |e| e.start_time),
+        &collect_column(intervals, |e| e.end_time),
+        &collect_column(intervals, |e| e.add_asset_liquidity_volume),
+        &collect_column(intervals, |e| e.add_rune_liquidity_volume),
+        &collect_column(intervals, |e| e.add_liquidity_volume),
+        &collect_column(intervals, |e| e.add_liquidity_volume_usd),
+        &collect_column(intervals, |e| e.add_liquidity_count),
+        &collect_column(intervals, |e| e.remove_asset_liquidity_volume),
+        &collect_column(intervals, |e| e.remove_rune_liquidity_volume),
+        &collect_column(intervals, |e| e.remove_liquidity_volume),
+        &collect_column(intervals, |e| e.remove_liquidity_volume_usd),
+        &collect_column(intervals, |e| e.remove_liquidity_count),
+        &collect_column(intervals, |e| e.net_change),
+        &collect_column(intervals, |e| e.net_change_usd),
+        &collect_column(intervals, |e| e.rune_price_usd),
+        &collect_column(intervals, |e| is_provisional(e.end_time, now)),
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.inserted).collect())
+}
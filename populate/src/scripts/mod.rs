@@ -1,6 +1,20 @@
+pub mod actions;
+pub mod archive;
+pub mod cron_job;
+pub mod dataset;
+pub mod dead_letters;
 pub mod depth_price_history;
 pub mod earnings_history;
+pub mod error;
+pub mod gaps;
+pub mod leader;
+pub mod liquidity_changes_history;
+pub mod network_snapshots;
+pub mod paging;
+pub mod pools;
 pub mod runepool_history;
+pub mod runs;
+pub mod savers_history;
 pub mod swaps_history;
+pub mod tvl_history;
 pub mod utils;
-pub mod cron_job;
\ No newline at end of file
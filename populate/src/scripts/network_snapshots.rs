@@ -0,0 +1,40 @@
+use super::error::IngestError;
+use super::runs::RunStats;
+use chrono::Utc;
+use midgard::{MidgardClient, Transport};
+use sqlx::PgPool;
+
+/// Stores the current `/v2/network` in `network_snapshots`.
+pub async fn snapshot<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let network = client.network().await?;
+    let inserted: Option<bool> = sqlx::query_scalar(
+        "
+    INSERT INTO network_snapshots (
+        snapshotTime, bondingApy, liquidityApy, totalReserve, totalPooledRune,
+        activeNodeCount, standbyNodeCount, activeBonds, totalActiveBond, totalStandbyBond,
+        nextChurnHeight
+    )
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+    ON CONFLICT (snapshotTime) DO NOTHING
+    RETURNING TRUE",
+    )
+    .bind(Utc::now().timestamp())
+    .bind(network.bonding_apy)
+    .bind(network.liquidity_apy)
+    .bind(network.total_reserve)
+    .bind(network.total_pooled_rune)
+    .bind(network.active_node_count)
+    .bind(network.standby_node_count)
+    .bind(&network.active_bonds)
+    .bind(network.active_bonds.iter().sum::<i64>())
+    .bind(network.standby_bonds.iter().sum::<i64>())
+    .bind(network.next_churn_height)
+    .fetch_optional(pool)
+    .await?;
+    stats.add_page(1, inserted.as_slice(), 0);
+    Ok(())
+}
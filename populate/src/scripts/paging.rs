@@ -0,0 +1,78 @@
+use super::archive::archive_page;
+use super::dead_letters::{resolve_dead_letters, save_dead_letters};
+use super::error::IngestError;
+use super::runs::RunStats;
+use super::utils::{collect_column, page_checkpoint, page_size, save_checkpoint};
+use chrono::Utc;
+use midgard::{History, Interval, MidgardError};
+use sqlx::{PgConnection, PgPool};
+use std::future::Future;
+use std::pin::Pin;
+
+/// The intervals of a history dataset that are paged through together: those of one pool
+/// for per-pool datasets, otherwise the whole dataset.
+#[derive(Clone, Copy, Debug)]
+pub struct Series<'a> {
+    pub dataset: &'static str,
+    pub pool: Option<&'a str>,
+}
+
+/// The upsert of a page of intervals, returning one flag per row written, `true` when it
+/// was inserted rather than updated. Boxed so the ingestion can be spawned onto the runtime.
+pub type PageWrite<'c> = Pin<Box<dyn Future<Output = Result<Vec<bool>, sqlx::Error>> + Send + 'c>>;
+
+/// Ingests the intervals of `series` starting in `[from, to)`. `fetch` requests the page
+/// of the given number of intervals from a start time, and `insert_page` upserts a page
+/// of intervals of the series' pool, given the time they are provisional against.
+///
+/// Each page is stored in its own transaction along with its archive and dead letters.
+/// With `save_progress` the checkpoint moves along with each page; backfills of older
+/// ranges leave it alone. Paging stops at `to`, on an empty page, or on a page that does
+/// not move past the time it was requested from.
+pub async fn ingest_range<I, Fut, W>(
+    pool: &PgPool,
+    series: Series<'_>,
+    (from, to): (i64, i64),
+    save_progress: bool,
+    stats: &mut RunStats,
+    fetch: impl Fn(i64, u32) -> Fut,
+    insert_page: W,
+) -> Result<(), IngestError>
+where
+    I: Interval,
+    Fut: Future<Output = Result<History<I>, MidgardError>>,
+    W: for<'c> Fn(&'c mut PgConnection, Option<&'c str>, &'c [I], i64) -> PageWrite<'c>,
+{
+    let Series {
+        dataset,
+        pool: pool_name,
+    } = series;
+    let now = Utc::now().timestamp();
+    let mut from_time = from;
+    loop {
+        let mut page = fetch(from_time, page_size(from_time, to)).await?;
+        let mut tx = pool.begin().await?;
+        archive_page(&mut tx, dataset, pool_name, &page).await?;
+        page.retain_before(to);
+        let Some(page_end) = page.last_end_time() else {
+            break;
+        };
+        save_dead_letters(&mut tx, dataset, pool_name, &page.url, &page.rejected).await?;
+
+        let written = insert_page(&mut tx, pool_name, &page.intervals, now).await?;
+        stats.add_page(page.intervals.len(), &written, page.rejected.len());
+        let start_times = collect_column(&page.intervals, |i| i.start_time());
+        resolve_dead_letters(&mut tx, dataset, pool_name, &start_times).await?;
+        if save_progress {
+            let checkpoint = page_checkpoint(&page.intervals, now).unwrap_or(from_time);
+            save_checkpoint(&mut tx, dataset, pool_name, checkpoint).await?;
+        }
+        tx.commit().await?;
+
+        if page_end >= to || page_end <= from_time {
+            break;
+        }
+        from_time = page_end;
+    }
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use super::error::IngestError;
+use super::runs::RunStats;
+use super::utils::collect_column;
+use chrono::Utc;
+use midgard::{MidgardClient, PoolDetail, Transport};
+use sqlx::{PgConnection, PgPool};
+
+/// Stores the current `/v2/pools` as a snapshot in `pool_snapshots` and refreshes `pools`,
+/// which keeps the latest state of every pool ever listed.
+pub async fn snapshot<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let pools = client.pools().await?;
+    let now = Utc::now().timestamp();
+    let mut tx = pool.begin().await?;
+    insert_snapshot(&mut tx, &pools, now).await?;
+    let written = upsert_pools(&mut tx, &pools, now).await?;
+    stats.add_page(pools.len(), &written, 0);
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn insert_snapshot(
+    conn: &mut PgConnection,
+    pools: &[PoolDetail],
+    now: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO pool_snapshots (
+            snapshotTime, asset, status, nativeDecimal, assetDepth, runeDepth, assetPrice,
+            assetPriceUsd, units, liquidityUnits, synthUnits, synthSupply, volume24h,
+            annualPercentageRate, poolApy, saversDepth, saversUnits, saversApr
+        )
+        SELECT $1, * FROM UNNEST(
+            $2::TEXT[], $3::TEXT[], $4::INT[], $5::BIGINT[], $6::BIGINT[], $7::FLOAT8[],
+            $8::FLOAT8[], $9::BIGINT[], $10::BIGINT[], $11::BIGINT[], $12::BIGINT[],
+            $13::BIGINT[], $14::FLOAT8[], $15::FLOAT8[], $16::BIGINT[], $17::BIGINT[],
+            $18::FLOAT8[]
+        )
+        ON CONFLICT (asset, snapshotTime) DO NOTHING
+        "#,
+        now,
+        &collect_column(pools, |p| p.asset.clone()),
+        &collect_column(pools, |p| p.status.clone()),
+        &collect_column(pools, |p| p.native_decimal) as &[Option<i32>],
+        &collect_column(pools, |p| p.asset_depth),
+        &collect_column(pools, |p| p.rune_depth),
+        &collect_column(pools, |p| p.asset_price),
+        &collect_column(pools, |p| p.asset_price_usd),
+        &collect_column(pools, |p| p.units),
+        &collect_column(pools, |p| p.liquidity_units),
+        &collect_column(pools, |p| p.synth_units),
+        &collect_column(pools, |p| p.synth_supply),
+        &collect_column(pools, |p| p.volume_24h),
+        &collect_column(pools, |p| p.annual_percentage_rate) as &[Option<f64>],
+        &collect_column(pools, |p| p.pool_apy) as &[Option<f64>],
+        &collect_column(pools, |p| p.savers_depth),
+        &collect_column(pools, |p| p.savers_units),
+        &collect_column(pools, |p| p.savers_apr) as &[Option<f64>],
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Returns one flag per pool, `true` when it was listed for the first time.
+async fn upsert_pools(
+    conn: &mut PgConnection,
+    pools: &[PoolDetail],
+    now: i64,
+) -> Result<Vec<bool>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        INSERT INTO pools (
+            asset, status, nativeDecimal, assetDepth, runeDepth, assetPrice, assetPriceUsd,
+            units, liquidityUnits, synthUnits, synthSupply, volume24h, annualPercentageRate,
+            poolApy, saversDepth, saversUnits, saversApr, firstSeen, lastSeen
+        )
+        SELECT *, $18, $18 FROM UNNEST(
+            $1::TEXT[], $2::TEXT[], $3::INT[], $4::BIGINT[], $5::BIGINT[], $6::FLOAT8[],
+            $7::FLOAT8[], $8::BIGINT[], $9::BIGINT[], $10::BIGINT[], $11::BIGINT[],
+            $12::BIGINT[], $13::FLOAT8[], $14::FLOAT8[], $15::BIGINT[], $16::BIGINT[],
+            $17::FLOAT8[]
+        )
+        ON CONFLICT (asset) DO UPDATE
+        SET status = EXCLUDED.status,
+            nativeDecimal = EXCLUDED.nativeDecimal,
+            assetDepth = EXCLUDED.assetDepth,
+            runeDepth = EXCLUDED.runeDepth,
+            assetPrice = EXCLUDED.assetPrice,
+            assetPriceUsd = EXCLUDED.assetPriceUsd,
+            units = EXCLUDED.units,
+            liquidityUnits = EXCLUDED.liquidityUnits,
+            synthUnits = EXCLUDED.synthUnits,
+            synthSupply = EXCLUDED.synthSupply,
+            volume24h = EXCLUDED.volume24h,
+            annualPercentageRate = EXCLUDED.annualPercentageRate,
+            poolApy = EXCLUDED.poolApy,
+            saversDepth = EXCLUDED.saversDepth,
+            saversUnits = EXCLUDED.saversUnits,
+            saversApr = EXCLUDED.saversApr,
+            lastSeen = EXCLUDED.lastSeen
+        RETURNING (xmax = 0) AS "inserted!"
+        "#,
+        &collect_column(pools, |p| p.asset.clone()),
+        &collect_column(pools, |p| p.status.clone()),
+        &collect_column(pools, |p| p.native_decimal) as &[Option<i32>],
+        &collect_column(pools, |p| p.asset_depth),
+        &collect_column(pools, |p| p.rune_depth),
+        &collect_column(pools, |p| p.asset_price),
+        &collect_column(pools, |p| p.asset_price_usd),
+        &collect_column(pools, |p| p.units),
+        &collect_column(pools, |p| p.liquidity_units),
+        &collect_column(pools, |p| p.synth_units),
+        &collect_column(pools, |p| p.synth_supply),
+        &collect_column(pools, |p| p.volume_24h),
+        &collect_column(pools, |p| p.annual_percentage_rate) as &[Option<f64>],
+        &collect_column(pools, |p| p.pool_apy) as &[Option<f64>],
+        &collect_column(pools, |p| p.savers_depth),
+        &collect_column(pools, |p| p.savers_units),
+        &collect_column(pools, |p| p.savers_apr) as &[Option<f64>],
+        now,
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.inserted).collect())
+}
@@ -1,69 +1,80 @@
-use super::utils::get_last_successful_entry_for_table;
-use crate::models::runepool_history::RunepoolHistory;
+use super::archive;
+use super::error::IngestError;
+use super::paging::{ingest_range, Series};
+use super::runs::RunStats;
+use super::utils::{collect_column, get_checkpoint, is_provisional};
 use chrono::Utc;
-use reqwest::get;
-use serde_json::Value;
-use sqlx::Error;
-pub async fn fetch_and_insert_data(pool: &sqlx::PgPool) -> Result<(), Error> {
-    let mut from_time = get_last_successful_entry_for_table(pool, "runepool_history").await;
-    let end_time = Utc::now().timestamp();
+use midgard::{MidgardClient, RunepoolHistoryInterval, Transport};
+use sqlx::{PgConnection, PgPool};
 
-    loop {
-        let url = format!(
-            "https://midgard.ninerealms.com/v2/history/runepool?interval=hour&from={}&count=400",
-            from_time
-        );
-        println!("{:?}", url);
-        let response: Value = get(&url).await.unwrap().json().await.unwrap();
+const DATASET: &str = "runepool_history";
 
-        if let Some(intervals) = response["intervals"].as_array() {
-            for entry in intervals {
-                let runepool_history = RunepoolHistory {
-                    id: None,
-                    startTime: entry["startTime"].as_str().unwrap().parse::<i64>().unwrap(),
-                    endTime: entry["endTime"].as_str().unwrap().parse::<i64>().unwrap(),
-                    units: entry["units"].as_str().unwrap().parse::<i64>().unwrap(),
-                    count: entry["count"].as_str().unwrap().parse::<i32>().unwrap(),
-                };
-                match sqlx::query!(
-                    r#"
-                    INSERT INTO runepool_history (startTime, endTime, units, count)
-                    VALUES ($1, $2, $3, $4)
-                    "#,
-                    runepool_history.startTime,
-                    runepool_history.endTime,
-                    runepool_history.units,
-                    runepool_history.count
-                )
-                .execute(pool)
-                .await
-                {
-                    Ok(_) => {}
-                    Err(e) => {
-                        if let sqlx::Error::Database(db_err) = &e {
-                            if db_err.code() == Some("23505".into()) {
-                                // duplicate key error
-                                continue;
-                            }
-                        }
-                        return Err(e);
-                    }
-                };
-            }
+pub async fn fetch_and_insert_data<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let from_time = get_checkpoint(pool, DATASET, None).await?;
+    fetch_and_insert_range(pool, client, from_time, Utc::now().timestamp(), true, stats).await
+}
+
+/// Ingests the RUNEPool intervals starting in `[from, to)`, see [`ingest_range`].
+pub async fn fetch_and_insert_range<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    from: i64,
+    to: i64,
+    save_progress: bool,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let series = Series {
+        dataset: DATASET,
+        pool: None,
+    };
+    ingest_range(
+        pool,
+        series,
+        (from, to),
+        save_progress,
+        stats,
+        |from_time, count| client.runepool_history(from_time, count),
+        |conn, _, intervals, now| Box::pin(insert_page(conn, intervals, now)),
+    )
+    .await
+}
+
+pub async fn reprocess(pool: &PgPool, stats: &mut RunStats) -> Result<(), IngestError> {
+    archive::reprocess(pool, DATASET, false, stats, |conn, _, intervals, now| {
+        Box::pin(insert_page(conn, intervals, now))
+    })
+    .await
+}
 
-            let last_entry = intervals.last().unwrap();
-            let last_end_time = last_entry["endTime"]
-                .as_str()
-                .unwrap()
-                .parse::<i64>()
-                .unwrap();
-            if last_end_time >= end_time {
-                break;
-            }
-            from_time = last_end_time;
-        } else {
-            break;
-        }
-    }
-    Ok(())
+/// Upserts a page of intervals; those ending within the hour of `now` stay provisional.
+/// Returns one flag per row written, `true` when it was inserted rather than updated.
+async fn insert_page(
+    conn: &mut PgConnection,
+    intervals: &[RunepoolHistoryInterval],
+    now: i64,
+) -> Result<Vec<bool>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        INSERT INTO runepool_history (startTime, endTime, units, count, provisional)
+        SELECT * FROM UNNEST($1::BIGINT[], $2::BIGINT[], $3::BIGINT[], $4::INT[], $5::BOOL[])
+        ON CONFLICT (startTime, endTime) DO UPDATE
+        SET units = EXCLUDED.units,
+            count = EXCLUDED.count,
+            provisional = EXCLUDED.provisional
+        WHERE runepool_history.provisional
+        RETURNING (xmax = 0) AS "inserted!"
+        "#,
+        &collect_column(intervals, |e| e.start_time),
+        &collect_column(intervals, |e| e.end_time),
+        &collect_column(intervals, |e| e.units),
+        &collect_column(intervals, |e| e.count),
+        &collect_column(intervals, |e| is_provisional(e.end_time, now)),
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.inserted).collect())
 }
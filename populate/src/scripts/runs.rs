@@ -0,0 +1,254 @@
+use super::dataset::Dataset;
+use super::error::IngestError;
+use super::gaps::refetch_gaps;
+use midgard::{MidgardClient, Transport};
+use serde::Serialize;
+use sqlx::PgPool;
+
+/// What an ingestion run was started for, stored in `ingestion_runs.kind`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunKind {
+    Sync,
+    Backfill,
+    Retry,
+    Reprocess,
+}
+
+impl RunKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunKind::Sync => "sync",
+            RunKind::Backfill => "backfill",
+            RunKind::Retry => "retry",
+            RunKind::Reprocess => "reprocess",
+        }
+    }
+}
+
+/// Counters of a run, accumulated page by page.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct RunStats {
+    pub pages_fetched: i32,
+    pub rows_inserted: i64,
+    pub rows_updated: i64,
+    pub rows_skipped: i64,
+    pub rows_rejected: i64,
+}
+
+impl RunStats {
+    /// Counts a page of `total` decoded intervals. `written` has one flag per row the
+    /// upsert wrote, `true` when it was inserted rather than updated; the other intervals
+    /// were already finalized and skipped.
+    pub fn add_page(&mut self, total: usize, written: &[bool], rejected: usize) {
+        let inserted = written.iter().filter(|inserted| **inserted).count();
+        self.pages_fetched += 1;
+        self.rows_inserted += inserted as i64;
+        self.rows_updated += (written.len() - inserted) as i64;
+        self.rows_skipped += total.saturating_sub(written.len()) as i64;
+        self.rows_rejected += rejected as i64;
+    }
+}
+
+/// Records the start of a run and returns its id. `leader_pid` is the lock session of the
+/// leader starting it, `None` outside the scheduler.
+pub async fn start_run(
+    pool: &PgPool,
+    dataset: Dataset,
+    kind: RunKind,
+    pool_name: Option<&str>,
+    range: Option<(i64, i64)>,
+    leader_pid: Option<i32>,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        "
+    INSERT INTO ingestion_runs (dataset, kind, pool, range_from, range_to, leader_pid)
+    VALUES ($1, $2, $3, $4, $5, $6)
+    RETURNING id",
+    )
+    .bind(dataset.table())
+    .bind(kind.as_str())
+    .bind(pool_name)
+    .bind(range.map(|(from, _)| from))
+    .bind(range.map(|(_, to)| to))
+    .bind(leader_pid)
+    .fetch_one(pool)
+    .await
+}
+
+/// Stores the outcome and counters of run `id`, unless it is no longer running: a run a
+/// new leader marked interrupted keeps that status.
+pub async fn finish_run(
+    pool: &PgPool,
+    id: i64,
+    stats: &RunStats,
+    result: &Result<(), IngestError>,
+) -> Result<(), sqlx::Error> {
+    let (status, error) = match result {
+        Ok(()) => ("succeeded", None),
+        Err(err) => ("failed", Some(err.to_string())),
+    };
+    sqlx::query(
+        "
+    UPDATE ingestion_runs
+    SET status = $2, error = $3, finished_at = NOW(), pages_fetched = $4,
+        rows_inserted = $5, rows_updated = $6, rows_skipped = $7, rows_rejected = $8
+    WHERE id = $1 AND status = 'running'",
+    )
+    .bind(id)
+    .bind(status)
+    .bind(error)
+    .bind(stats.pages_fetched)
+    .bind(stats.rows_inserted)
+    .bind(stats.rows_updated)
+    .bind(stats.rows_skipped)
+    .bind(stats.rows_rejected)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Ingests `dataset` from its checkpoint, then re-fetches its gaps, as one recorded run.
+/// `leader_pid` is the lock session of the leader running it, `None` from the CLI.
+pub async fn sync<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    dataset: Dataset,
+    leader_pid: Option<i32>,
+) -> Result<RunStats, IngestError> {
+    let id = start_run(pool, dataset, RunKind::Sync, None, None, leader_pid).await?;
+    execute_sync(pool, client, dataset, id).await
+}
+
+/// Re-fetches `[from, to)` of `dataset` as one recorded run.
+pub async fn backfill<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    dataset: Dataset,
+    kind: RunKind,
+    pool_name: Option<&str>,
+    from: i64,
+    to: i64,
+) -> Result<RunStats, IngestError> {
+    let id = start_run(pool, dataset, kind, pool_name, Some((from, to)), None).await?;
+    execute_backfill(pool, client, dataset, id, pool_name, from, to).await
+}
+
+/// Rewrites `dataset` from the archived pages as one recorded run.
+pub async fn reprocess(pool: &PgPool, dataset: Dataset) -> Result<RunStats, IngestError> {
+    let id = start_run(pool, dataset, RunKind::Reprocess, None, None, None).await?;
+    let mut stats = RunStats::default();
+    let result = dataset.reprocess(pool, &mut stats).await;
+    finish_run(pool, id, &stats, &result).await?;
+    result.map(|_| stats)
+}
+
+/// Queues a run of `kind` for the scheduler of whichever instance leads `dataset`. It
+/// re-fetches `range` when given, and syncs from the checkpoint otherwise. Returns the run
+/// id to poll.
+pub async fn enqueue_run(
+    pool: &PgPool,
+    dataset: Dataset,
+    kind: RunKind,
+    pool_name: Option<&str>,
+    range: Option<(i64, i64)>,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        "
+    INSERT INTO ingestion_runs (dataset, kind, pool, range_from, range_to, status, queued_at)
+    VALUES ($1, $2, $3, $4, $5, 'queued', NOW())
+    RETURNING id",
+    )
+    .bind(dataset.table())
+    .bind(kind.as_str())
+    .bind(pool_name)
+    .bind(range.map(|(from, _)| from))
+    .bind(range.map(|(_, to)| to))
+    .fetch_one(pool)
+    .await
+}
+
+/// A queued run claimed by the leader of its dataset.
+#[derive(sqlx::FromRow)]
+pub struct QueuedRun {
+    pub id: i64,
+    pub pool: Option<String>,
+    pub range_from: Option<i64>,
+    pub range_to: Option<i64>,
+}
+
+/// Claims the oldest queued run of `dataset` for the leader with lock session `leader_pid`,
+/// if any.
+pub async fn claim_queued(
+    pool: &PgPool,
+    dataset: Dataset,
+    leader_pid: i32,
+) -> Result<Option<QueuedRun>, sqlx::Error> {
+    sqlx::query_as(
+        "
+    UPDATE ingestion_runs SET status = 'running', started_at = NOW(), leader_pid = $2
+    WHERE id = (
+        SELECT id FROM ingestion_runs
+        WHERE dataset = $1 AND status = 'queued'
+        ORDER BY id
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+    )
+    RETURNING id, pool, range_from, range_to",
+    )
+    .bind(dataset.table())
+    .bind(leader_pid)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Executes a claimed run: a backfill of its range when it has one, otherwise a sync.
+pub async fn run_queued<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    dataset: Dataset,
+    run: QueuedRun,
+) -> Result<RunStats, IngestError> {
+    println!("Running queued run {} of {}", run.id, dataset);
+    match (run.range_from, run.range_to) {
+        (Some(from), Some(to)) => {
+            execute_backfill(pool, client, dataset, run.id, run.pool.as_deref(), from, to).await
+        }
+        _ => execute_sync(pool, client, dataset, run.id).await,
+    }
+}
+
+async fn execute_sync<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    dataset: Dataset,
+    id: i64,
+) -> Result<RunStats, IngestError> {
+    let mut stats = RunStats::default();
+    let mut result = dataset
+        .fetch_and_insert_data(pool, client, &mut stats)
+        .await;
+    if result.is_ok() {
+        result = refetch_gaps(pool, client, dataset, &mut stats)
+            .await
+            .map(|_| ());
+    }
+    finish_run(pool, id, &stats, &result).await?;
+    result.map(|_| stats)
+}
+
+async fn execute_backfill<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    dataset: Dataset,
+    id: i64,
+    pool_name: Option<&str>,
+    from: i64,
+    to: i64,
+) -> Result<RunStats, IngestError> {
+    let mut stats = RunStats::default();
+    let result = dataset
+        .backfill(pool, client, pool_name, from, to, &mut stats)
+        .await;
+    finish_run(pool, id, &stats, &result).await?;
+    result.map(|_| stats)
+}
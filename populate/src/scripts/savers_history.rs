@@ -0,0 +1,126 @@
+use super::archive;
+use super::error::IngestError;
+use super::paging::{ingest_range, Series};
+use super::runs::RunStats;
+use super::utils::{collect_column, get_checkpoint, get_pools, is_provisional};
+use chrono::Utc;
+use midgard::{MidgardClient, SaversHistoryInterval, Transport};
+use sqlx::{PgConnection, PgPool};
+
+const DATASET: &str = "savers_history";
+
+pub async fn fetch_and_insert_data<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    // A failing pool does not hold up the others, but fails the run once they are done.
+    let mut failures = Vec::new();
+    for pool_name in get_pools(client).await? {
+        if let Err(err) = fetch_and_insert_pool_data(pool, client, &pool_name, stats).await {
+            eprintln!("Error running savers_history for {}: {}", pool_name, err);
+            failures.push((pool_name, err));
+        }
+    }
+    IngestError::check_pools(failures)
+}
+
+async fn fetch_and_insert_pool_data<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    pool_name: &str,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let from_time = get_checkpoint(pool, DATASET, Some(pool_name)).await?;
+    let to = Utc::now().timestamp();
+    fetch_and_insert_range(pool, client, pool_name, from_time, to, true, stats).await
+}
+
+/// Ingests the savers intervals of `pool_name` starting in `[from, to)`, see
+/// [`ingest_range`].
+pub async fn fetch_and_insert_range<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    pool_name: &str,
+    from: i64,
+    to: i64,
+    save_progress: bool,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let series = Series {
+        dataset: DATASET,
+        pool: Some(pool_name),
+    };
+    ingest_range(
+        pool,
+        series,
+        (from, to),
+        save_progress,
+        stats,
+        |from_time, count| client.savers_history(pool_name, from_time, count),
+        |conn, pool_name, intervals, now| {
+            Box::pin(insert_page(
+                conn,
+                pool_name.unwrap_or_default(),
+                intervals,
+                now,
+            ))
+        },
+    )
+    .await
+}
+
+pub async fn reprocess(pool: &PgPool, stats: &mut RunStats) -> Result<(), IngestError> {
+    archive::reprocess(
+        pool,
+        DATASET,
+        true,
+        stats,
+        |conn, pool_name, intervals, now| {
+            Box::pin(insert_page(
+                conn,
+                pool_name.unwrap_or_default(),
+                intervals,
+                now,
+            ))
+        },
+    )
+    .await
+}
+
+/// Upserts a page of intervals; those ending within the hour of `now` stay provisional.
+/// Returns one flag per row written, `true` when it was inserted rather than updated.
+async fn insert_page(
+    conn: &mut PgConnection,
+    pool_name: &str,
+    intervals: &[SaversHistoryInterval],
+    now: i64,
+) -> Result<Vec<bool>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        INSERT INTO savers_history (
+            pool, startTime, endTime, saversCount, saversDepth, saversUnits, provisional
+        )
+        SELECT $1, * FROM UNNEST(
+            $2::BIGINT[], $3::BIGINT[], $4::BIGINT[], $5::BIGINT[], $6::BIGINT[], $7::BOOL[]
+        )
+        ON CONFLICT (pool, startTime, endTime) DO UPDATE
+        SET saversCount = EXCLUDED.saversCount,
+            saversDepth = EXCLUDED.saversDepth,
+            saversUnits = EXCLUDED.saversUnits,
+            provisional = EXCLUDED.provisional
+        WHERE savers_history.provisional
+        RETURNING (xmax = 0) AS "inserted!"
+        "#,
+        pool_name,
+        &collect_column(intervals, |e| e.start_time),
+        &collect_column(intervals, |e| e.end_time),
+        &collect_column(intervals, |e| e.savers_count),
+        &collect_column(intervals, |e| e.savers_depth),
+        &collect_column(intervals, |e| e.savers_units),
+        &collect_column(intervals, |e| is_provisional(e.end_time, now)),
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.inserted).collect())
+}
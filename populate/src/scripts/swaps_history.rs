@@ -1,296 +1,166 @@
-use bigdecimal::BigDecimal;
+use super::archive;
+use super::error::IngestError;
+use super::paging::{ingest_range, Series};
+use super::runs::RunStats;
+use super::utils::{collect_column, get_checkpoint, is_provisional, to_decimal};
 use chrono::Utc;
-use reqwest::get;
-use serde_json::Value;
-use sqlx::Error;
-use std::str::FromStr;
+use midgard::{MidgardClient, SwapHistoryInterval, Transport};
+use sqlx::{PgConnection, PgPool};
 
-use crate::models::swaps_history::SwapsHistory;
+const DATASET: &str = "swap_history";
 
-use super::utils::get_last_successful_entry_for_table;
-
-pub async fn fetch_and_insert_data(pool: &sqlx::PgPool) -> Result<(), Error> {
-    let mut from_time = get_last_successful_entry_for_table(pool, "swaps_history").await;
-    let end_time = Utc::now().timestamp();
-
-    loop {
-        let url = format!(
-            "https://midgard.ninerealms.com/v2/history/swaps?interval=hour&from={}&count=100",
-            from_time
-        );
-        println!("{:?}", url);
-
-        let response: Value = get(&url).await.unwrap().json().await.unwrap();
-
-        if let Some(intervals) = response["intervals"].as_array() {
-            for entry in intervals {
-                let swap_history = SwapsHistory {
-                    id: None,
-                    startTime: entry["startTime"].as_str().unwrap().parse::<i64>().unwrap(),
-                    endTime: entry["endTime"].as_str().unwrap().parse::<i64>().unwrap(),
-                    toAssetCount: entry["toAssetCount"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    toRuneCount: entry["toRuneCount"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    toTradeCount: entry["toTradeCount"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    fromTradeCount: entry["fromTradeCount"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    synthMintCount: entry["synthMintCount"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    synthRedeemCount: entry["synthRedeemCount"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    totalCount: entry["totalCount"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    toAssetVolume: entry["toAssetVolume"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    toRuneVolume: entry["toRuneVolume"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    toTradeVolume: entry["toTradeVolume"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    fromTradeVolume: entry["fromTradeVolume"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    synthMintVolume: entry["synthMintVolume"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    synthRedeemVolume: entry["synthRedeemVolume"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    totalVolume: entry["totalVolume"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    toAssetVolumeUSD: entry["toAssetVolumeUSD"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    toRuneVolumeUSD: entry["toRuneVolumeUSD"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    toTradeVolumeUSD: entry["toTradeVolumeUSD"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    fromTradeVolumeUSD: entry["fromTradeVolumeUSD"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    synthMintVolumeUSD: entry["synthMintVolumeUSD"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    synthRedeemVolumeUSD: entry["synthRedeemVolumeUSD"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    totalVolumeUSD: entry["totalVolumeUSD"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    toAssetFees: entry["toAssetFees"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    toRuneFees: entry["toRuneFees"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    toTradeFees: entry["toTradeFees"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    fromTradeFees: entry["fromTradeFees"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    synthMintFees: entry["synthMintFees"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    synthRedeemFees: entry["synthRedeemFees"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<i64>()
-                        .unwrap(),
-                    totalFees: entry["totalFees"].as_str().unwrap().parse::<i64>().unwrap(),
-                    toAssetAverageSlip: entry["toAssetAverageSlip"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    toRuneAverageSlip: entry["toRuneAverageSlip"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    toTradeAverageSlip: entry["toTradeAverageSlip"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    fromTradeAverageSlip: entry["fromTradeAverageSlip"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    synthMintAverageSlip: entry["synthMintAverageSlip"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    synthRedeemAverageSlip: entry["synthRedeemAverageSlip"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    averageSlip: entry["averageSlip"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                    runePriceUSD: entry["runePriceUSD"]
-                        .as_str()
-                        .unwrap()
-                        .parse::<f64>()
-                        .unwrap(),
-                };
-                println!("{:?}", swap_history);
-                match sqlx::query!(
-                    r#"
-                    INSERT INTO swap_history (
-                        startTime, endTime, toAssetCount, toRuneCount, toTradeCount, fromTradeCount,
-                        synthMintCount, synthRedeemCount, totalCount, toAssetVolume, toRuneVolume,
-                        toTradeVolume, fromTradeVolume, synthMintVolume, synthRedeemVolume, totalVolume,
-                        toAssetVolumeUSD, toRuneVolumeUSD, toTradeVolumeUSD, fromTradeVolumeUSD,
-                        synthMintVolumeUSD, synthRedeemVolumeUSD, totalVolumeUSD, toAssetFees, toRuneFees,
-                        toTradeFees, fromTradeFees, synthMintFees, synthRedeemFees, totalFees, 
-                        toAssetAverageSlip, toRuneAverageSlip, toTradeAverageSlip, fromTradeAverageSlip, 
-                        synthMintAverageSlip, synthRedeemAverageSlip, averageSlip, runePriceUSD
-                    )
-                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, 
-                            $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29, $30, $31, $32, $33, $34, $35, $36, $37, $38)
-                    "#,
-                    swap_history.startTime,
-                    swap_history.endTime,
-                    swap_history.toAssetCount,
-                    swap_history.toRuneCount,
-                    swap_history.toTradeCount,
-                    swap_history.fromTradeCount,
-                    swap_history.synthMintCount,
-                    swap_history.synthRedeemCount,
-                    swap_history.totalCount,
-                    swap_history.toAssetVolume,
-                    swap_history.toRuneVolume,
-                    swap_history.toTradeVolume,
-                    swap_history.fromTradeVolume,
-                    swap_history.synthMintVolume,
-                    swap_history.synthRedeemVolume,
-                    swap_history.totalVolume,
-                    BigDecimal::from_str(&swap_history.toAssetVolumeUSD.to_string()).unwrap(),
-                    BigDecimal::from_str(&swap_history.toRuneVolumeUSD.to_string()).unwrap(),
-                    BigDecimal::from_str(&swap_history.toTradeVolumeUSD.to_string()).unwrap(),
-                    BigDecimal::from_str(&swap_history.fromTradeVolumeUSD.to_string()).unwrap(),
-                    BigDecimal::from_str(&swap_history.synthMintVolumeUSD.to_string()).unwrap(),
-                    BigDecimal::from_str(&swap_history.synthRedeemVolumeUSD.to_string()).unwrap(),
-                    BigDecimal::from_str(&swap_history.totalVolumeUSD.to_string()).unwrap(),
-                    swap_history.toAssetFees,
-                    swap_history.toRuneFees,
-                    swap_history.toTradeFees,
-                    swap_history.fromTradeFees,
-                    swap_history.synthMintFees,
-                    swap_history.synthRedeemFees,
-                    swap_history.totalFees,
-                    BigDecimal::from_str(&swap_history.toAssetAverageSlip.to_string()).unwrap(),
-                    BigDecimal::from_str(&swap_history.toRuneAverageSlip.to_string()).unwrap(),
-                    BigDecimal::from_str(&swap_history.toTradeAverageSlip.to_string()).unwrap(),
-                    BigDecimal::from_str(&swap_history.fromTradeAverageSlip.to_string()).unwrap(),
-                    BigDecimal::from_str(&swap_history.synthMintAverageSlip.to_string()).unwrap(),
-                    BigDecimal::from_str(&swap_history.synthRedeemAverageSlip.to_string()).unwrap(),
-                    BigDecimal::from_str(&swap_history.averageSlip.to_string()).unwrap(),
-                    BigDecimal::from_str(&swap_history.runePriceUSD.to_string()).unwrap(),
-                )
-                .execute(pool)
-                .await
-                {
-                    Ok(_) => {}
-                    Err(e) => {
-                        if let sqlx::Error::Database(db_err) = &e {
-                            if db_err.code() == Some("23505".into()) {
-                                // Skip this entry due to duplicate key error
-                                continue;
-                            }
-                        }
-                        return Err(e);
-                    }
-                };
-            }
+pub async fn fetch_and_insert_data<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let from_time = get_checkpoint(pool, DATASET, None).await?;
+    fetch_and_insert_range(pool, client, from_time, Utc::now().timestamp(), true, stats).await
+}
 
-            let last_entry = intervals.last().unwrap();
-            let last_end_time = last_entry["endTime"]
-                .as_str()
-                .unwrap()
-                .parse::<i64>()
-                .unwrap();
+/// Ingests the swap intervals starting in `[from, to)`, see [`ingest_range`].
+pub async fn fetch_and_insert_range<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    from: i64,
+    to: i64,
+    save_progress: bool,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let series = Series {
+        dataset: DATASET,
+        pool: None,
+    };
+    ingest_range(
+        pool,
+        series,
+        (from, to),
+        save_progress,
+        stats,
+        |from_time, count| client.swaps_history(from_time, count),
+        |conn, _, intervals, now| Box::pin(insert_page(conn, intervals, now)),
+    )
+    .await
+}
 
-            if last_end_time >= end_time {
-                break;
-            }
+pub async fn reprocess(pool: &PgPool, stats: &mut RunStats) -> Result<(), IngestError> {
+    archive::reprocess(pool, DATASET, false, stats, |conn, _, intervals, now| {
+        Box::pin(insert_page(conn, intervals, now))
+    })
+    .await
+}
 
-            from_time = last_end_time;
-        } else {
-            break;
-        }
-    }
-    Ok(())
+/// Upserts a page of intervals; those ending within the hour of `now` stay provisional.
+/// Returns one flag per row written, `true` when it was inserted rather than updated.
+async fn insert_page(
+    conn: &mut PgConnection,
+    intervals: &[SwapHistoryInterval],
+    now: i64,
+) -> Result<Vec<bool>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        INSERT INTO swap_history (
+            startTime, endTime, toAssetCount, toRuneCount, toTradeCount, fromTradeCount,
+            synthMintCount, synthRedeemCount, totalCount, toAssetVolume, toRuneVolume,
+            toTradeVolume, fromTradeVolume, synthMintVolume, synthRedeemVolume, totalVolume,
+            toAssetVolumeUSD, toRuneVolumeUSD, toTradeVolumeUSD, fromTradeVolumeUSD,
+            synthMintVolumeUSD, synthRedeemVolumeUSD, totalVolumeUSD, toAssetFees, toRuneFees,
+            toTradeFees, fromTradeFees, synthMintFees, synthRedeemFees, totalFees,
+            toAssetAverageSlip, toRuneAverageSlip, toTradeAverageSlip, fromTradeAverageSlip,
+            synthMintAverageSlip, synthRedeemAverageSlip, averageSlip, runePriceUSD, provisional
+        )
+        SELECT * FROM UNNEST(
+            $1::BIGINT[], $2::BIGINT[], $3::BIGINT[], $4::BIGINT[], $5::BIGINT[],
+            $6::BIGINT[], $7::BIGINT[], $8::BIGINT[], $9::BIGINT[], $10::BIGINT[],
+            $11::BIGINT[], $12::BIGINT[], $13::BIGINT[], $14::BIGINT[], $15::BIGINT[],
+            $16::BIGINT[], $17::NUMERIC[], $18::NUMERIC[], $19::NUMERIC[], $20::NUMERIC[],
+            $21::NUMERIC[], $22::NUMERIC[], $23::NUMERIC[], $24::BIGINT[], $25::BIGINT[],
+            $26::BIGINT[], $27::BIGINT[], $28::BIGINT[], $29::BIGINT[], $30::BIGINT[],
+            $31::NUMERIC[], $32::NUMERIC[], $33::NUMERIC[], $34::NUMERIC[], $35::NUMERIC[],
+            $36::NUMERIC[], $37::NUMERIC[], $38::NUMERIC[], $39::BOOL[]
+        )
+        ON CONFLICT (startTime, endTime) DO UPDATE
+        SET toAssetCount = EXCLUDED.toAssetCount,
+            toRuneCount = EXCLUDED.toRuneCount,
+            toTradeCount = EXCLUDED.toTradeCount,
+            fromTradeCount = EXCLUDED.fromTradeCount,
+            synthMintCount = EXCLUDED.synthMintCount,
+            synthRedeemCount = EXCLUDED.synthRedeemCount,
+            totalCount = EXCLUDED.totalCount,
+            toAssetVolume = EXCLUDED.toAssetVolume,
+            toRuneVolume = EXCLUDED.toRuneVolume,
+            toTradeVolume = EXCLUDED.toTradeVolume,
+            fromTradeVolume = EXCLUDED.fromTradeVolume,
+            synthMintVolume = EXCLUDED.synthMintVolume,
+            synthRedeemVolume = EXCLUDED.synthRedeemVolume,
+            totalVolume = EXCLUDED.totalVolume,
+            toAssetVolumeUSD = EXCLUDED.toAssetVolumeUSD,
+            toRuneVolumeUSD = EXCLUDED.toRuneVolumeUSD,
+            toTradeVolumeUSD = EXCLUDED.toTradeVolumeUSD,
+            fromTradeVolumeUSD = EXCLUDED.fromTradeVolumeUSD,
+            synthMintVolumeUSD = EXCLUDED.synthMintVolumeUSD,
+            synthRedeemVolumeUSD = EXCLUDED.synthRedeemVolumeUSD,
+            totalVolumeUSD = EXCLUDED.totalVolumeUSD,
+            toAssetFees = EXCLUDED.toAssetFees,
+            toRuneFees = EXCLUDED.toRuneFees,
+            toTradeFees = EXCLUDED.toTradeFees,
+            fromTradeFees = EXCLUDED.fromTradeFees,
+            synthMintFees = EXCLUDED.synthMintFees,
+            synthRedeemFees = EXCLUDED.synthRedeemFees,
+            totalFees = EXCLUDED.totalFees,
+            toAssetAverageSlip = EXCLUDED.toAssetAverageSlip,
+            toRuneAverageSlip = EXCLUDED.toRuneAverageSlip,
+            toTradeAverageSlip = EXCLUDED.toTradeAverageSlip,
+            fromTradeAverageSlip = EXCLUDED.fromTradeAverageSlip,
+            synthMintAverageSlip = EXCLUDED.synthMintAverageSlip,
+            synthRedeemAverageSlip = EXCLUDED.synthRedeemAverageSlip,
+            averageSlip = EXCLUDED.averageSlip,
+            runePriceUSD = EXCLUDED.runePriceUSD,
+            provisional = EXCLUDED.provisional
+        WHERE swap_history.provisional
+        RETURNING (xmax = 0) AS "inserted!"
+        "#,
+        &collect_column(intervals, |e| e.start_time),
+        &collect_column(intervals, |e| e.end_time),
+        &collect_column(intervals, |e| e.to_asset_count),
+        &collect_column(intervals, |e| e.to_rune_count),
+        &collect_column(intervals, |e| e.to_trade_count),
+        &collect_column(intervals, |e| e.from_trade_count),
+        &collect_column(intervals, |e| e.synth_mint_count),
+        &collect_column(intervals, |e| e.synth_redeem_count),
+        &collect_column(intervals, |e| e.total_count),
+        &collect_column(intervals, |e| e.to_asset_volume),
+        &collect_column(intervals, |e| e.to_rune_volume),
+        &collect_column(intervals, |e| e.to_trade_volume),
+        &collect_column(intervals, |e| e.from_trade_volume),
+        &collect_column(intervals, |e| e.synth_mint_volume),
+        &collect_column(intervals, |e| e.synth_redeem_volume),
+        &collect_column(intervals, |e| e.total_volume),
+        &collect_column(intervals, |e| to_decimal(e.to_asset_volume_usd)),
+        &collect_column(intervals, |e| to_decimal(e.to_rune_volume_usd)),
+        &collect_column(intervals, |e| to_decimal(e.to_trade_volume_usd)),
+        &collect_column(intervals, |e| to_decimal(e.from_trade_volume_usd)),
+        &collect_column(intervals, |e| to_decimal(e.synth_mint_volume_usd)),
+        &collect_column(intervals, |e| to_decimal(e.synth_redeem_volume_usd)),
+        &collect_column(intervals, |e| to_decimal(e.total_volume_usd)),
+        &collect_column(intervals, |e| e.to_asset_fees),
+        &collect_column(intervals, |e| e.to_rune_fees),
+        &collect_column(intervals, |e| e.to_trade_fees),
+        &collect_column(intervals, |e| e.from_trade_fees),
+        &collect_column(intervals, |e| e.synth_mint_fees),
+        &collect_column(intervals, |e| e.synth_redeem_fees),
+        &collect_column(intervals, |e| e.total_fees),
+        &collect_column(intervals, |e| to_decimal(e.to_asset_average_slip)),
+        &collect_column(intervals, |e| to_decimal(e.to_rune_average_slip)),
+        &collect_column(intervals, |e| to_decimal(e.to_trade_average_slip)),
+        &collect_column(intervals, |e| to_decimal(e.from_trade_average_slip)),
+        &collect_column(intervals, |e| to_decimal(e.synth_mint_average_slip)),
+        &collect_column(intervals, |e| to_decimal(e.synth_redeem_average_slip)),
+        &collect_column(intervals, |e| to_decimal(e.average_slip)),
+        &collect_column(intervals, |e| to_decimal(e.rune_price_usd)),
+        &collect_column(intervals, |e| is_provisional(e.end_time, now)),
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.inserted).collect())
 }
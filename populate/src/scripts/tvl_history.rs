@@ -0,0 +1,137 @@
+use super::archive;
+use super::error::IngestError;
+use super::paging::{ingest_range, Series};
+use super::runs::RunStats;
+use super::utils::{collect_column, get_checkpoint, is_provisional};
+use chrono::Utc;
+use midgard::{MidgardClient, Transport, TvlHistoryInterval, TvlHistoryPoolDepth};
+use sqlx::{Error, PgConnection, PgPool};
+
+const DATASET: &str = "tvl_history";
+
+pub async fn fetch_and_insert_data<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let from_time = get_checkpoint(pool, DATASET, None).await?;
+    fetch_and_insert_range(pool, client, from_time, Utc::now().timestamp(), true, stats).await
+}
+
+/// Ingests the TVL intervals starting in `[from, to)`, see [`ingest_range`].
+pub async fn fetch_and_insert_range<T: Transport>(
+    pool: &PgPool,
+    client: &MidgardClient<T>,
+    from: i64,
+    to: i64,
+    save_progress: bool,
+    stats: &mut RunStats,
+) -> Result<(), IngestError> {
+    let series = Series {
+        dataset: DATASET,
+        pool: None,
+    };
+    ingest_range(
+        pool,
+        series,
+        (from, to),
+        save_progress,
+        stats,
+        |from_time, count| client.tvl_history(from_time, count),
+        |conn, _, intervals, now| Box::pin(insert_page(conn, intervals, now)),
+    )
+    .await
+}
+
+pub async fn reprocess(pool: &PgPool, stats: &mut RunStats) -> Result<(), IngestError> {
+    archive::reprocess(pool, DATASET, false, stats, |conn, _, intervals, now| {
+        Box::pin(insert_page(conn, intervals, now))
+    })
+    .await
+}
+
+/// Upserts a page of intervals; those ending within the hour of `now` stay provisional.
+/// Returns one flag per interval written, `true` when it was inserted rather than updated.
+async fn insert_page(
+    conn: &mut PgConnection,
+    intervals: &[TvlHistoryInterval],
+    now: i64,
+) -> Result<Vec<bool>, Error> {
+    let mut written = Vec::new();
+    for interval in intervals {
+        // A finalized interval that is already stored keeps its pool depths as they are.
+        let provisional = is_provisional(interval.end_time, now);
+        if let Some((tvl_history_id, inserted)) =
+            upsert_tvl_interval(&mut *conn, interval, provisional).await?
+        {
+            upsert_pool_depths(&mut *conn, tvl_history_id, &interval.pools_depth).await?;
+            written.push(inserted);
+        }
+    }
+    Ok(written)
+}
+
+async fn upsert_pool_depths(
+    conn: &mut PgConnection,
+    tvl_history_id: i32,
+    pools: &[TvlHistoryPoolDepth],
+) -> Result<(), Error> {
+    let pool_names = collect_column(pools, |p| p.pool.clone());
+    sqlx::query!(
+        "DELETE FROM tvl_history_pools WHERE tvl_history_id = $1 AND pool <> ALL($2)",
+        tvl_history_id,
+        &pool_names,
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO tvl_history_pools (tvl_history_id, pool, totaldepth)
+        SELECT $1, * FROM UNNEST($2::TEXT[], $3::BIGINT[])
+        ON CONFLICT (tvl_history_id, pool) DO UPDATE
+        SET totaldepth = EXCLUDED.totaldepth
+        "#,
+        tvl_history_id,
+        &pool_names,
+        &collect_column(pools, |p| p.total_depth),
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Returns the id of the inserted or refreshed row and whether it was inserted, or `None`
+/// if the interval is already stored and finalized.
+async fn upsert_tvl_interval(
+    conn: &mut PgConnection,
+    interval: &TvlHistoryInterval,
+    provisional: bool,
+) -> Result<Option<(i32, bool)>, Error> {
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO tvl_history (
+            startTime, endTime, totalValuePooled, totalValueBonded, totalValueLocked,
+            runePriceUsd, provisional
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (startTime, endTime) DO UPDATE
+        SET totalValuePooled = EXCLUDED.totalValuePooled,
+            totalValueBonded = EXCLUDED.totalValueBonded,
+            totalValueLocked = EXCLUDED.totalValueLocked,
+            runePriceUsd = EXCLUDED.runePriceUsd,
+            provisional = EXCLUDED.provisional
+        WHERE tvl_history.provisional
+        RETURNING id, (xmax = 0) AS "inserted!"
+        "#,
+        interval.start_time,
+        interval.end_time,
+        interval.total_value_pooled,
+        interval.total_value_bonded,
+        interval.total_value_locked,
+        interval.rune_price_usd,
+        provisional,
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    Ok(row.map(|row| (row.id, row.inserted)))
+}
@@ -1,26 +1,122 @@
+use super::error::IngestError;
+use bigdecimal::BigDecimal;
 use chrono::NaiveDate;
-use sqlx::Row;
+use midgard::{Interval, MidgardClient, MidgardError, Transport};
+use sqlx::{PgConnection, PgPool};
+use std::str::FromStr;
 
-pub async fn get_last_successful_entry_for_table(pool: &sqlx::PgPool, table_name: &str) -> i64 {
-    let default_timestamp = NaiveDate::from_ymd_opt(2024, 10, 1)
+fn default_start_timestamp() -> i64 {
+    NaiveDate::from_ymd_opt(2024, 10, 1)
         .unwrap_or_else(|| panic!("Invalid date"))
         .and_hms_opt(0, 0, 0)
         .unwrap()
-        .timestamp();
-
-    let query = format!(
-        "SELECT COALESCE(MAX(endtime), $1) as last_successful_entry FROM {}",
-        table_name
-    );
-    println!("{:?}", query);
-    match sqlx::query(&query)
-        .bind(default_timestamp)
+        .and_utc()
+        .timestamp()
+}
+
+/// Where ingestion of `dataset` (optionally for a single pool) should resume from.
+///
+/// `dataset` is the name of the table the dataset is written to; a missing table is an
+/// error rather than a silent restart from the default start date.
+pub async fn get_checkpoint(
+    pool: &PgPool,
+    dataset: &str,
+    pool_name: Option<&str>,
+) -> Result<i64, IngestError> {
+    let table_exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+        .bind(dataset)
         .fetch_one(pool)
-        .await
-    {
-        Ok(row) => row
-            .get::<Option<i64>, _>("last_successful_entry")
-            .unwrap_or(default_timestamp),
-        Err(_) => default_timestamp,
+        .await?;
+    if !table_exists {
+        return Err(IngestError::MissingTable(dataset.to_string()));
+    }
+
+    let last_end_time: Option<i64> = sqlx::query_scalar(
+        "SELECT last_end_time FROM ingestion_state WHERE dataset = $1 AND pool = $2",
+    )
+    .bind(dataset)
+    .bind(pool_name.unwrap_or(""))
+    .fetch_optional(pool)
+    .await?;
+    let from_time = last_end_time.unwrap_or_else(default_start_timestamp);
+    println!("Resuming {} {:?} from {}", dataset, pool_name, from_time);
+    Ok(from_time)
+}
+
+/// Moves the checkpoint of `dataset` forward. Meant to run in the transaction that
+/// inserts the page ending at `last_end_time`.
+pub async fn save_checkpoint(
+    conn: &mut PgConnection,
+    dataset: &str,
+    pool_name: Option<&str>,
+    last_end_time: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "
+    INSERT INTO ingestion_state (dataset, pool, last_end_time, updated_at)
+    VALUES ($1, $2, $3, NOW())
+    ON CONFLICT (dataset, pool) DO UPDATE
+    SET last_end_time = GREATEST(ingestion_state.last_end_time, EXCLUDED.last_end_time),
+        updated_at = NOW()",
+    )
+    .bind(dataset)
+    .bind(pool_name.unwrap_or(""))
+    .bind(last_end_time)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// An interval is provisional while Midgard may still add to it: its `endTime` is in
+/// the future or within the current hour.
+pub fn is_provisional(end_time: i64, now: i64) -> bool {
+    end_time >= now - now.rem_euclid(3600)
+}
+
+/// Where the next run should resume after `intervals` were stored: the end of the last
+/// finalized interval, so that provisional ones are fetched (and upserted) again.
+pub fn page_checkpoint<I: Interval>(intervals: &[I], now: i64) -> Option<i64> {
+    match intervals.iter().find(|i| is_provisional(i.end_time(), now)) {
+        Some(first_provisional) => Some(first_provisional.start_time()),
+        None => intervals.last().map(|i| i.end_time()),
     }
 }
+
+/// Number of hourly intervals to request from `from` so that a page does not run past
+/// `to`, capped at Midgard's maximum of 400.
+pub fn page_size(from: i64, to: i64) -> u32 {
+    ((to - from + 3599) / 3600).clamp(1, 400) as u32
+}
+
+/// Pools to ingest per-pool datasets for. Taken from the comma-separated
+/// `MIDGARD_POOLS` env var when set, otherwise discovered from `/v2/pools`.
+pub async fn get_pools<T: Transport>(
+    client: &MidgardClient<T>,
+) -> Result<Vec<String>, MidgardError> {
+    if let Ok(pools) = std::env::var("MIDGARD_POOLS") {
+        return Ok(pools
+            .split(',')
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect());
+    }
+
+    Ok(client
+        .pools()
+        .await?
+        .into_iter()
+        .map(|pool| pool.asset)
+        .collect())
+}
+
+/// Converts a value decoded by the Midgard client into a DECIMAL bind parameter.
+/// The client rejects NaN and infinities, and `f64`'s `Display` never uses exponent
+/// notation, so the string always parses.
+pub fn to_decimal(value: f64) -> BigDecimal {
+    BigDecimal::from_str(&value.to_string()).expect("finite f64 formats as a decimal")
+}
+
+/// Collects one field of every interval in a page, for binding as an `UNNEST` array.
+pub fn collect_column<T, U>(rows: &[T], field: impl Fn(&T) -> U) -> Vec<U> {
+    rows.iter().map(field).collect()
+}
@@ -0,0 +1,117 @@
+//! Runs the windowed paging of actions against Midgard pages recorded in `tests/fixtures`,
+//! on a scratch database migrated from `../migrations`.
+
+use midgard::{FixtureTransport, MidgardClient};
+use populate::scripts::actions::{fetch_and_insert_range, reprocess};
+use populate::RunStats;
+use sqlx::PgPool;
+
+const T0: i64 = 1704067200; // 2024-01-01T00:00:00Z
+const HOUR: i64 = 3600;
+const DAY: i64 = 24 * HOUR;
+
+fn client() -> MidgardClient<FixtureTransport> {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+    MidgardClient::with_transport(FixtureTransport::new(dir))
+}
+
+async fn stored_txids(pool: &PgPool) -> Vec<String> {
+    sqlx::query_scalar("SELECT txid FROM actions ORDER BY date")
+        .fetch_all(pool)
+        .await
+        .unwrap()
+}
+
+async fn checkpoint(pool: &PgPool) -> Option<i64> {
+    sqlx::query_scalar("SELECT last_end_time FROM ingestion_state WHERE dataset = 'actions'")
+        .fetch_optional(pool)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn pages_day_windows_oldest_first_and_holds_at_pending(pool: PgPool) {
+    let mut stats = RunStats::default();
+    fetch_and_insert_range(&pool, &client(), T0, T0 + DAY + 12 * HOUR, true, &mut stats)
+        .await
+        .unwrap();
+
+    assert_eq!(stats.pages_fetched, 2);
+    assert_eq!(stored_txids(&pool).await, vec!["A1", "A2", "A3"]);
+    assert_eq!(checkpoint(&pool).await, Some(T0 + HOUR));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn stops_holding_at_actions_pending_for_days(pool: PgPool) {
+    let mut stats = RunStats::default();
+    fetch_and_insert_range(
+        &pool,
+        &client(),
+        T0,
+        T0 + 3 * DAY + 12 * HOUR,
+        true,
+        &mut stats,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(stored_txids(&pool).await, vec!["A1", "A2"]);
+    assert_eq!(checkpoint(&pool).await, Some(T0 + 3 * DAY));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn keeps_the_progress_of_stored_windows_when_a_later_one_fails(pool: PgPool) {
+    let from = T0 + 4 * DAY;
+    let mut stats = RunStats::default();
+    let result = fetch_and_insert_range(
+        &pool,
+        &client(),
+        from,
+        from + DAY + 12 * HOUR,
+        true,
+        &mut stats,
+    )
+    .await;
+
+    assert!(result.is_err());
+    assert_eq!(stored_txids(&pool).await, vec!["B1"]);
+    assert_eq!(checkpoint(&pool).await, Some(from + DAY));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn stores_actions_without_a_txid_apart(pool: PgPool) {
+    let from = T0 + 8 * DAY;
+    let mut stats = RunStats::default();
+    fetch_and_insert_range(&pool, &client(), from, from + 12 * HOUR, false, &mut stats)
+        .await
+        .unwrap();
+
+    assert_eq!(stats.rows_inserted, 2);
+    assert_eq!(stored_txids(&pool).await, vec!["", ""]);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn reprocess_rewrites_successful_actions(pool: PgPool) {
+    let mut stats = RunStats::default();
+    fetch_and_insert_range(&pool, &client(), T0, T0 + DAY, false, &mut stats)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE actions SET height = 0")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let mut stats = RunStats::default();
+    reprocess(&pool, &mut stats).await.unwrap();
+
+    assert_eq!(stats.rows_updated, 2);
+    let heights: Vec<(String, i64)> =
+        sqlx::query_as("SELECT txid, height FROM actions ORDER BY date")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        heights,
+        vec![("A1".to_string(), 1800), ("A2".to_string(), 5400)]
+    );
+}
@@ -0,0 +1,48 @@
+//! Runs the leader's lock session and run bookkeeping on a scratch database migrated from
+//! `../migrations`.
+
+use populate::scripts::leader::{acquire_leadership, while_leader};
+use populate::scripts::runs::{finish_run, start_run, RunKind};
+use populate::{Dataset, RunStats};
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::time::timeout;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn stops_the_run_when_the_lock_session_dies(pool: PgPool) {
+    let mut leader = acquire_leadership(&pool, Dataset::RunepoolHistory).await;
+    sqlx::query("SELECT pg_terminate_backend($1)")
+        .bind(leader.pid)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let result = timeout(
+        Duration::from_secs(10),
+        while_leader(&mut leader.conn, std::future::pending::<()>()),
+    )
+    .await
+    .expect("the lock check notices");
+    assert_eq!(result, None);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn keeps_the_status_of_a_run_marked_interrupted(pool: PgPool) {
+    let dataset = Dataset::RunepoolHistory;
+    let id = start_run(&pool, dataset, RunKind::Sync, None, None, Some(1))
+        .await
+        .unwrap();
+    // A new leader took over while the run was still going.
+    acquire_leadership(&pool, dataset).await;
+
+    finish_run(&pool, id, &RunStats::default(), &Ok(()))
+        .await
+        .unwrap();
+
+    let status: String = sqlx::query_scalar("SELECT status FROM ingestion_runs WHERE id = $1")
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "failed");
+}
@@ -0,0 +1,134 @@
+//! Runs the paging of a history dataset against Midgard pages recorded in
+//! `tests/fixtures`, on a scratch database migrated from `../migrations`. A request for a
+//! page that was not recorded fails the test, so each one also checks where paging stops.
+
+use midgard::{FixtureTransport, MidgardClient};
+use populate::scripts::runepool_history::{fetch_and_insert_range, reprocess};
+use populate::RunStats;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::time::timeout;
+
+const T0: i64 = 1704067200; // 2024-01-01T00:00:00Z
+const HOUR: i64 = 3600;
+
+fn client() -> MidgardClient<FixtureTransport> {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+    MidgardClient::with_transport(FixtureTransport::new(dir))
+}
+
+async fn ingest(pool: &PgPool, from: i64, to: i64, save_progress: bool) -> RunStats {
+    let mut stats = RunStats::default();
+    // A loop that keeps requesting the same page would never return.
+    timeout(
+        Duration::from_secs(10),
+        fetch_and_insert_range(pool, &client(), from, to, save_progress, &mut stats),
+    )
+    .await
+    .expect("paging stops")
+    .expect("the range is ingested");
+    stats
+}
+
+async fn stored_start_times(pool: &PgPool) -> Vec<i64> {
+    sqlx::query_scalar("SELECT startTime FROM runepool_history ORDER BY startTime")
+        .fetch_all(pool)
+        .await
+        .unwrap()
+}
+
+async fn checkpoint(pool: &PgPool) -> Option<i64> {
+    sqlx::query_scalar(
+        "SELECT last_end_time FROM ingestion_state WHERE dataset = 'runepool_history'",
+    )
+    .fetch_optional(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn follows_short_pages_and_advances_the_checkpoint(pool: PgPool) {
+    let stats = ingest(&pool, T0, T0 + 3 * HOUR, true).await;
+
+    assert_eq!(stats.pages_fetched, 2);
+    assert_eq!(stats.rows_inserted, 3);
+    assert_eq!(
+        stored_start_times(&pool).await,
+        vec![T0, T0 + HOUR, T0 + 2 * HOUR]
+    );
+    assert_eq!(checkpoint(&pool).await, Some(T0 + 3 * HOUR));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn backfill_leaves_the_checkpoint_alone(pool: PgPool) {
+    let stats = ingest(&pool, T0, T0 + 3 * HOUR, false).await;
+
+    assert_eq!(stats.rows_inserted, 3);
+    assert_eq!(checkpoint(&pool).await, None);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn drops_intervals_starting_at_or_after_to(pool: PgPool) {
+    let from = T0 + 24 * HOUR;
+    let stats = ingest(&pool, from, from + 2 * HOUR, true).await;
+
+    assert_eq!(stats.pages_fetched, 1);
+    assert_eq!(stored_start_times(&pool).await, vec![from, from + HOUR]);
+    assert_eq!(checkpoint(&pool).await, Some(from + 2 * HOUR));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn stops_on_an_empty_page(pool: PgPool) {
+    let from = T0 + 48 * HOUR;
+    let stats = ingest(&pool, from, from + 2 * HOUR, true).await;
+
+    assert_eq!(stats.pages_fetched, 0);
+    assert!(stored_start_times(&pool).await.is_empty());
+    assert_eq!(checkpoint(&pool).await, None);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn stops_when_a_page_ends_at_from(pool: PgPool) {
+    let from = T0 + 72 * HOUR;
+    let stats = ingest(&pool, from, from + 3 * HOUR, true).await;
+
+    assert_eq!(stats.pages_fetched, 1);
+    assert_eq!(stored_start_times(&pool).await, vec![from - HOUR]);
+    assert_eq!(checkpoint(&pool).await, Some(from));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn reprocess_replays_the_archived_pages(pool: PgPool) {
+    ingest(&pool, T0, T0 + 3 * HOUR, true).await;
+    sqlx::query("DELETE FROM runepool_history")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let mut stats = RunStats::default();
+    reprocess(&pool, &mut stats).await.unwrap();
+
+    assert_eq!(stats.pages_fetched, 2);
+    assert_eq!(stats.rows_inserted, 3);
+    assert_eq!(
+        stored_start_times(&pool).await,
+        vec![T0, T0 + HOUR, T0 + 2 * HOUR]
+    );
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn reprocess_keeps_the_attempts_of_dead_letters(pool: PgPool) {
+    let from = T0 + 96 * HOUR;
+    ingest(&pool, from, from + 2 * HOUR, true).await;
+
+    let mut stats = RunStats::default();
+    reprocess(&pool, &mut stats).await.unwrap();
+
+    let attempts: Vec<(i64, i32)> =
+        sqlx::query_as("SELECT start_time, attempts FROM ingestion_dead_letters")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(attempts, vec![(from, 1)]);
+    assert_eq!(stored_start_times(&pool).await, vec![from + HOUR]);
+}
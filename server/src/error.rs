@@ -0,0 +1,224 @@
+// src/error.rs
+use actix_web::error::PathError;
+use actix_web::http::header::WWW_AUTHENTICATE;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use midgard::MidgardError;
+use populate::IngestError;
+use std::fmt;
+
+/// An error a handler responds with, rendered as an RFC 7807 `application/problem+json`
+/// body. `code` is stable for clients to match on. Database, upstream and internal failures
+/// are logged, and their details are left out of the response.
+#[derive(Debug)]
+pub enum ApiError {
+    /// The request's parameters were rejected.
+    Validation {
+        code: &'static str,
+        detail: String,
+    },
+    /// The requested resource does not exist.
+    NotFound {
+        code: &'static str,
+        detail: String,
+    },
+    /// The request lacks a valid admin token.
+    Unauthorized,
+    /// Midgard failed while serving the request.
+    Upstream(MidgardError),
+    Database(sqlx::Error),
+    /// The server is misconfigured or failed in a way the client cannot fix.
+    Internal {
+        code: &'static str,
+        detail: String,
+    },
+}
+
+// The problem `type` of internal errors; the others are plain HTTP errors (`about:blank`).
+const INTERNAL_ERROR_TYPE: &str = "/problems/internal-error";
+
+impl ApiError {
+    pub fn validation(code: &'static str, detail: impl Into<String>) -> Self {
+        ApiError::Validation {
+            code,
+            detail: detail.into(),
+        }
+    }
+
+    pub fn not_found(code: &'static str, detail: impl Into<String>) -> Self {
+        ApiError::NotFound {
+            code,
+            detail: detail.into(),
+        }
+    }
+
+    pub fn internal(code: &'static str, detail: impl Into<String>) -> Self {
+        ApiError::Internal {
+            code,
+            detail: detail.into(),
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::Validation { code, .. }
+            | ApiError::NotFound { code, .. }
+            | ApiError::Internal { code, .. } => code,
+            ApiError::Unauthorized => "unauthorized",
+            ApiError::Upstream(_) => "upstream_error",
+            ApiError::Database(_) => "database_error",
+        }
+    }
+
+    // What the client is told; the Display output may hold internal details.
+    fn detail(&self) -> String {
+        match self {
+            ApiError::Validation { detail, .. } | ApiError::NotFound { detail, .. } => {
+                detail.clone()
+            }
+            ApiError::Unauthorized => "A valid admin token is required".to_string(),
+            ApiError::Upstream(_) => "Midgard could not serve the request".to_string(),
+            ApiError::Database(_) => "The database query failed".to_string(),
+            ApiError::Internal { .. } => "The server could not handle the request".to_string(),
+        }
+    }
+
+    fn problem_type(&self) -> &'static str {
+        match self {
+            ApiError::Internal { .. } => INTERNAL_ERROR_TYPE,
+            _ => "about:blank",
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Validation { code, detail }
+            | ApiError::NotFound { code, detail }
+            | ApiError::Internal { code, detail } => {
+                write!(f, "{}: {}", code, detail)
+            }
+            ApiError::Unauthorized => write!(f, "Unauthorized"),
+            ApiError::Upstream(err) => write!(f, "{}", err),
+            ApiError::Database(err) => write!(f, "Database error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApiError::Upstream(err) => Some(err),
+            ApiError::Database(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::Validation { .. } => StatusCode::BAD_REQUEST,
+            ApiError::NotFound { .. } => StatusCode::NOT_FOUND,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Database(_) | ApiError::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if matches!(
+            self,
+            ApiError::Upstream(_) | ApiError::Database(_) | ApiError::Internal { .. }
+        ) {
+            eprintln!("Request failed: {}", self);
+        }
+        let status = self.status_code();
+        let mut response = HttpResponse::build(status);
+        response.content_type("application/problem+json");
+        if let ApiError::Unauthorized = self {
+            response.insert_header((WWW_AUTHENTICATE, "Bearer"));
+        }
+        response.json(serde_json::json!({
+            "type": self.problem_type(),
+            "title": status.canonical_reason().unwrap_or("Error"),
+            "status": status.as_u16(),
+            "detail": self.detail(),
+            "code": self.code(),
+        }))
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(err: sqlx::Error) -> Self {
+        ApiError::Database(err)
+    }
+}
+
+impl From<MidgardError> for ApiError {
+    fn from(err: MidgardError) -> Self {
+        ApiError::Upstream(err)
+    }
+}
+
+impl From<IngestError> for ApiError {
+    fn from(err: IngestError) -> Self {
+        match err {
+            IngestError::Midgard(err) => ApiError::Upstream(err),
+            IngestError::Database(err) => ApiError::Database(err),
+            // The migrations were not run: a server fault the client cannot fix.
+            err @ IngestError::MissingTable(_) => {
+                ApiError::internal("missing_table", err.to_string())
+            }
+            err @ IngestError::DeadLetterNotFound(_) => {
+                ApiError::not_found("dead_letter_not_found", err.to_string())
+            }
+            err @ IngestError::NotRetryable(_) => {
+                ApiError::validation("not_retryable", err.to_string())
+            }
+            err @ IngestError::NoHistory(_) => ApiError::validation("no_history", err.to_string()),
+            // Only a sync of every pool fails this way, which the server never runs inline.
+            err @ IngestError::Pools(_) => ApiError::internal("pools_failed", err.to_string()),
+            // Only runs started from the CLI take the lock this way.
+            err @ IngestError::Locked(_) => ApiError::validation("dataset_locked", err.to_string()),
+        }
+    }
+}
+
+/// A path segment that does not deserialize, e.g. a non-numeric id.
+impl From<PathError> for ApiError {
+    fn from(err: PathError) -> Self {
+        let detail = match err {
+            PathError::Deserialize(err) => err.to_string(),
+            err => err.to_string(),
+        };
+        ApiError::validation("invalid_path", detail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+
+    #[actix_web::test]
+    async fn renders_a_missing_table_as_an_internal_error() {
+        let err = ApiError::from(IngestError::MissingTable("tvl_history".to_string()));
+        let response = err.error_response();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "type": "/problems/internal-error",
+                "title": "Internal Server Error",
+                "status": 500,
+                "detail": "The server could not handle the request",
+                "code": "missing_table",
+            })
+        );
+    }
+}
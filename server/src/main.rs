@@ -1,11 +1,30 @@
 // src/main.rs
+pub mod error;
 pub mod models;
+pub mod query;
 pub mod routes;
+use actix_web::middleware::from_fn;
 use actix_web::{web, App, HttpServer};
-use populate::scripts::cron_job::start_cron_job;
+use error::ApiError;
+use midgard::MidgardClient;
+use populate::{start_cron_job, SchedulerState};
+use query::Query;
 use routes::{
-    depth_price_history::get_depth_price_history, earnings_history::get_earning_history,
-    runepool_history::get_runepool_history, swaps_history::get_swap_history,
+    actions::get_actions,
+    auth::require_admin_token,
+    dead_letters::{get_dead_letters, post_retry_dead_letter},
+    depth_price_history::get_depth_price_history,
+    earnings_history::get_earning_history,
+    ingest::post_ingest,
+    ingestion_runs::{get_ingestion_run, get_ingestion_runs},
+    liquidity_changes_history::get_liquidity_changes_history,
+    network_history::get_network_history,
+    pools::{get_pool, get_pools},
+    runepool_history::get_runepool_history,
+    savers_history::get_savers_history,
+    schedule::get_schedule,
+    swaps_history::get_swap_history,
+    tvl_history::get_tvl_history,
 };
 use shared::create_db_pool;
 
@@ -21,22 +40,55 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let pool_clone = pool.clone();
 
     let pool = web::Data::new(pool);
+    let schedule = SchedulerState::default();
+    let schedule_clone = schedule.clone();
+    let schedule = web::Data::new(schedule);
 
-    // Spawn the cron job (no need for another runtime, Actix uses tokio)
-    tokio::spawn(async move {
-        println!("Starting cron job");
-        start_cron_job(pool_clone).await;
-    });
+    // Spawn the cron job (no need for another runtime, Actix uses tokio). With several
+    // replicas only one ingests each dataset at a time; EMBEDDED_CRON=false turns it off,
+    // e.g. when the populate binary runs the schedule instead.
+    let embedded_cron = std::env::var("EMBEDDED_CRON")
+        .map(|value| !matches!(value.to_lowercase().as_str(), "false" | "0" | "no" | "off"))
+        .unwrap_or(true);
+    if embedded_cron {
+        tokio::spawn(async move {
+            start_cron_job(pool_clone, MidgardClient::from_env(), schedule_clone).await;
+        });
+    } else {
+        println!("Embedded cron disabled by EMBEDDED_CRON");
+    }
     let port = std::env::var("PORT").unwrap_or_else(|_| "8080".to_string());
     let _ = HttpServer::new(move || {
         App::new()
             .app_data(pool.clone())
+            .app_data(schedule.clone())
+            // Malformed path segments get the same problem+json bodies as the handlers' own
+            // errors; query strings are extracted by `query::Query`, which does the same.
+            .app_data(
+                web::PathConfig::default().error_handler(|err, _req| ApiError::from(err).into()),
+            )
             .service(web::resource("/").to(|| async { "Hello, world!" }))
+            .route("/schedule", web::get().to(get_schedule))
+            .route("/actions", web::get().to(get_actions))
+            .route("/pools", web::get().to(get_pools))
+            .route("/pools/{asset}", web::get().to(get_pool))
+            .service(
+                web::scope("/admin")
+                    .wrap(from_fn(require_admin_token))
+                    .route("/dead_letters", web::get().to(get_dead_letters))
+                    .route(
+                        "/dead_letters/{id}/retry",
+                        web::post().to(post_retry_dead_letter),
+                    )
+                    .route("/ingest/{dataset}", web::post().to(post_ingest))
+                    .route("/ingestion/runs", web::get().to(get_ingestion_runs))
+                    .route("/ingestion/runs/{id}", web::get().to(get_ingestion_run)),
+            )
             .route(
                 "/runepool_history",
                 web::get().to({
                     let value = pool.clone();
-                    move |query: web::Query<models::runepool_history::QueryParams>| {
+                    move |query: Query<models::runepool_history::QueryParams>| {
                         get_runepool_history(value.clone(), query)
                     }
                 }),
@@ -45,7 +97,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "/depth_history",
                 web::get().to({
                     let value = pool.clone();
-                    move |query: web::Query<models::depth_price_history::QueryParams>| {
+                    move |query: Query<models::depth_price_history::QueryParams>| {
                         get_depth_price_history(value.clone(), query)
                     }
                 }),
@@ -54,7 +106,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "/swaps_history",
                 web::get().to({
                     let value = pool.clone();
-                    move |query: web::Query<models::swap_history::SwapQueryParams>| {
+                    move |query: Query<models::swap_history::SwapQueryParams>| {
                         get_swap_history(value.clone(), query)
                     }
                 }),
@@ -63,11 +115,47 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "/earnings_history",
                 web::get().to({
                     let value = pool.clone();
-                    move |query: web::Query<models::earnings_history::EarningHistoryQueryParams>| {
+                    move |query: Query<models::earnings_history::EarningHistoryQueryParams>| {
                         get_earning_history(value.clone(), query)
                     }
                 }),
             )
+            .route(
+                "/tvl_history",
+                web::get().to({
+                    let value = pool.clone();
+                    move |query: Query<models::tvl_history::TvlQueryParams>| {
+                        get_tvl_history(value.clone(), query)
+                    }
+                }),
+            )
+            .route(
+                "/liquidity_changes_history",
+                web::get().to({
+                    let value = pool.clone();
+                    move |query: Query<
+                        models::liquidity_changes_history::LiquidityChangesQueryParams,
+                    >| { get_liquidity_changes_history(value.clone(), query) }
+                }),
+            )
+            .route(
+                "/network_history",
+                web::get().to({
+                    let value = pool.clone();
+                    move |query: Query<models::network_history::NetworkQueryParams>| {
+                        get_network_history(value.clone(), query)
+                    }
+                }),
+            )
+            .route(
+                "/savers_history",
+                web::get().to({
+                    let value = pool.clone();
+                    move |query: Query<models::savers_history::SaversQueryParams>| {
+                        get_savers_history(value.clone(), query)
+                    }
+                }),
+            )
     })
     .bind(format!("0.0.0.0:{}", port))?
     .run()
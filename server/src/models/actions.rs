@@ -0,0 +1,37 @@
+// src/models/actions.rs
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::prelude::FromRow;
+
+#[derive(Serialize, Deserialize, FromRow, Debug)]
+pub struct Action {
+    pub date: i64, // UNIX timestamp in nanoseconds
+    pub height: i64,
+    #[sqlx(rename = "type")]
+    #[serde(rename = "type")]
+    pub action_type: String, // swap, addLiquidity, withdraw, refund, ...
+    pub status: String, // success or pending
+    pub txid: String,   // First inbound tx id, empty when there is none
+    pub pools: Vec<String>,
+    #[sqlx(rename = "in_coins")]
+    #[serde(rename = "in")]
+    pub inbound: Value, // [{"address", "txID", "coins": [{"asset", "amount"}]}]
+    #[sqlx(rename = "out_coins")]
+    #[serde(rename = "out")]
+    pub outbound: Value,
+    pub metadata: Option<Value>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ActionQueryParams {
+    pub address: Option<String>, // Sender or recipient
+    pub txid: Option<String>,    // Inbound or outbound tx id
+    #[serde(rename = "type")]
+    pub action_type: Option<String>, // One or more comma-separated types, e.g. swap,withdraw
+    pub pool: Option<String>,
+    pub from: Option<i64>, // UNIX timestamp (seconds), inclusive
+    pub to: Option<i64>,   // UNIX timestamp (seconds), exclusive
+    pub order: Option<String>,
+    pub page: Option<i32>,
+    pub limit: Option<i32>,
+}
@@ -0,0 +1,28 @@
+// src/models/dead_letters.rs
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+#[derive(Serialize, FromRow, Debug)]
+pub struct DeadLetter {
+    pub id: i64,
+    pub dataset: String,            // Target table, e.g. swap_history
+    pub pool: String,               // Pool for per-pool datasets, '' for network-wide ones
+    pub start_time: Option<i64>,    // startTime of the interval, if it could be read
+    pub end_time: Option<i64>,      // endTime of the interval, if it could be read
+    pub url: String,                // Page the interval came from
+    pub payload: serde_json::Value, // The interval as returned by Midgard
+    pub error: String,              // Why it was rejected (latest attempt)
+    pub attempts: i32,
+    pub first_seen_at: i64,       // UNIX timestamp (seconds)
+    pub last_seen_at: i64,        // UNIX timestamp (seconds)
+    pub resolved_at: Option<i64>, // UNIX timestamp (seconds), once ingested
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeadLetterQueryParams {
+    pub dataset: Option<String>,
+    pub pool: Option<String>,
+    pub resolved: Option<bool>, // Defaults to unresolved only
+    pub page: Option<i32>,
+    pub limit: Option<i32>,
+}
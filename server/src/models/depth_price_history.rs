@@ -4,6 +4,7 @@ use sqlx::prelude::FromRow; // This is to handle DECIMAL(18, 8) type in the data
 
 #[derive(Serialize, Deserialize, FromRow, Debug)]
 pub struct DepthPriceHistory {
+    pub pool: String,              // The pool asset, e.g. BTC.BTC
     pub assetdepth: i64,           // The asset depth
     pub assetprice: f64,       // The asset price
     pub assetpriceusd: f64,    // The asset price in USD
@@ -29,6 +30,7 @@ pub struct QueryParams {
     pub page: Option<i32>,
     pub limit: Option<i32>,
     pub count: Option<i32>,
+    pub pool: Option<String>,      // One or more comma-separated pools, e.g. BTC.BTC,ETH.ETH
 
     // Dynamic conditions for the columns
     pub assetdepth_gt: Option<i64>,
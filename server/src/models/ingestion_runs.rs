@@ -0,0 +1,40 @@
+// src/models/ingestion_runs.rs
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+#[derive(Serialize, FromRow, Debug)]
+pub struct IngestionRun {
+    pub id: i64,
+    pub dataset: String,         // Target table, e.g. swap_history
+    pub kind: String,            // sync, backfill, retry or reprocess
+    pub pool: Option<String>,    // Pool the run was limited to, if any
+    pub range_from: Option<i64>, // Requested range (UNIX seconds), absent for syncs
+    pub range_to: Option<i64>,
+    pub status: String,           // queued, running, succeeded or failed
+    pub queued_at: Option<i64>,   // UNIX timestamp (seconds), for runs requested through the API
+    pub started_at: i64,          // UNIX timestamp (seconds)
+    pub finished_at: Option<i64>, // UNIX timestamp (seconds)
+    pub pages_fetched: i32,
+    pub rows_inserted: i64,
+    pub rows_updated: i64,
+    pub rows_skipped: i64,
+    pub rows_rejected: i64,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct IngestionRunQueryParams {
+    pub dataset: Option<String>,
+    pub kind: Option<String>,
+    pub status: Option<String>,
+    pub latest: Option<bool>, // Only the most recent run of each dataset
+    pub page: Option<i32>,
+    pub limit: Option<i32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct IngestRequest {
+    pub from: Option<i64>, // Backfill start (UNIX seconds); a sync from the checkpoint without it
+    pub to: Option<i64>,   // Backfill end (UNIX seconds), defaults to now
+    pub pool: Option<String>, // Only this pool, for per-pool datasets
+}
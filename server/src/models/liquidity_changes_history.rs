@@ -0,0 +1,46 @@
+// src/models/liquidity_changes_history.rs
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+#[derive(Serialize, Deserialize, FromRow, Debug)]
+pub struct LiquidityChangesHistory {
+    pub pool: Option<String>, // None for the network-wide totals
+    pub starttime: i64,
+    pub endtime: i64,
+    pub addassetliquidityvolume: i64,
+    pub addruneliquidityvolume: i64,
+    pub addliquidityvolume: i64,
+    pub addliquidityvolumeusd: f64,
+    pub addliquiditycount: i64,
+    pub removeassetliquidityvolume: i64,
+    pub removeruneliquidityvolume: i64,
+    pub removeliquidityvolume: i64,
+    pub removeliquidityvolumeusd: f64,
+    pub removeliquiditycount: i64,
+    pub netchange: i64,
+    pub netchangeusd: f64,
+    pub runepriceusd: f64, // At the end of the interval
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LiquidityChangesQueryParams {
+    pub interval: Option<String>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub pool: Option<String>, // One or more comma-separated pools; network-wide totals when absent
+    pub sort_by: Option<String>,
+    pub order: Option<String>,
+    pub page: Option<i32>,
+    pub limit: Option<i32>,
+    pub count: Option<i32>,
+    pub add_liquidity_count_gt: Option<i64>,
+    pub add_liquidity_count_lt: Option<i64>,
+    pub remove_liquidity_count_gt: Option<i64>,
+    pub remove_liquidity_count_lt: Option<i64>,
+    pub add_liquidity_volume_gt: Option<i64>,
+    pub add_liquidity_volume_lt: Option<i64>,
+    pub remove_liquidity_volume_gt: Option<i64>,
+    pub remove_liquidity_volume_lt: Option<i64>,
+    pub net_change_gt: Option<i64>,
+    pub net_change_lt: Option<i64>,
+}
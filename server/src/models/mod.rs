@@ -1,5 +1,13 @@
 // src/models/mod.rs
+pub mod actions;
 pub mod runepool_history;
 pub mod depth_price_history;
 pub mod swap_history;
-pub mod earnings_history;
\ No newline at end of file
+pub mod earnings_history;
+pub mod liquidity_changes_history;
+pub mod network_history;
+pub mod pools;
+pub mod savers_history;
+pub mod tvl_history;
+pub mod dead_letters;
+pub mod ingestion_runs;
\ No newline at end of file
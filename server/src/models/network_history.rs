@@ -0,0 +1,37 @@
+// src/models/network_history.rs
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+#[derive(Serialize, Deserialize, FromRow, Debug)]
+pub struct NetworkHistory {
+    pub starttime: i64,    // Start of the bracket
+    pub snapshottime: i64, // Time of the bracket's last snapshot, whose values are reported
+    pub bondingapy: f64,
+    pub liquidityapy: f64,
+    pub totalreserve: i64,
+    pub totalpooledrune: i64,
+    pub activenodecount: i32,
+    pub standbynodecount: i32,
+    pub activebonds: Vec<i64>,
+    pub totalactivebond: i64,
+    pub totalstandbybond: i64,
+    pub nextchurnheight: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NetworkQueryParams {
+    pub interval: Option<String>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub sort_by: Option<String>,
+    pub order: Option<String>,
+    pub page: Option<i32>,
+    pub limit: Option<i32>,
+    pub count: Option<i32>,
+    pub bonding_apy_gt: Option<f64>,
+    pub bonding_apy_lt: Option<f64>,
+    pub liquidity_apy_gt: Option<f64>,
+    pub liquidity_apy_lt: Option<f64>,
+    pub active_node_count_gt: Option<i32>,
+    pub active_node_count_lt: Option<i32>,
+}
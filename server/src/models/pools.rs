@@ -0,0 +1,68 @@
+// src/models/pools.rs
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+#[derive(Serialize, Deserialize, FromRow, Debug)]
+pub struct Pool {
+    pub asset: String,              // e.g. BTC.BTC
+    pub status: String,             // available, staged or suspended
+    pub nativedecimal: Option<i32>, // Decimals of the asset on its chain, if known
+    pub assetdepth: i64,
+    pub runedepth: i64,
+    pub assetprice: f64,
+    pub assetpriceusd: f64,
+    pub units: i64,
+    pub liquidityunits: i64,
+    pub synthunits: i64,
+    pub synthsupply: i64,
+    pub volume24h: i64,
+    pub annualpercentagerate: Option<f64>,
+    pub poolapy: Option<f64>,
+    pub saversdepth: i64,
+    pub saversunits: i64,
+    pub saversapr: Option<f64>,
+    pub firstseen: i64, // UNIX timestamp (seconds) of the first snapshot listing the pool
+    pub lastseen: i64,  // UNIX timestamp (seconds) of the latest snapshot listing the pool
+}
+
+#[derive(Serialize, Deserialize, FromRow, Debug)]
+pub struct PoolSnapshot {
+    pub snapshottime: i64, // UNIX timestamp (seconds)
+    pub status: String,
+    pub assetdepth: i64,
+    pub runedepth: i64,
+    pub assetprice: f64,
+    pub assetpriceusd: f64,
+    pub units: i64,
+    pub liquidityunits: i64,
+    pub volume24h: i64,
+    pub annualpercentagerate: Option<f64>,
+    pub poolapy: Option<f64>,
+    pub saversdepth: i64,
+    pub saversapr: Option<f64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PoolWithSnapshots {
+    #[serde(flatten)]
+    pub pool: Pool,
+    pub snapshots: Vec<PoolSnapshot>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PoolQueryParams {
+    pub status: Option<String>, // One or more comma-separated statuses, e.g. available,staged
+    pub sort_by: Option<String>,
+    pub order: Option<String>,
+    pub page: Option<i32>,
+    pub limit: Option<i32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PoolSnapshotQueryParams {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub order: Option<String>,
+    pub page: Option<i32>,
+    pub limit: Option<i32>,
+}
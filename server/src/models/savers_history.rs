@@ -0,0 +1,34 @@
+// src/models/savers_history.rs
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+#[derive(Serialize, Deserialize, FromRow, Debug)]
+pub struct SaversHistory {
+    pub pool: String,
+    pub starttime: i64,
+    pub endtime: i64,
+    pub saverscount: i64,
+    pub saversdepth: i64, // In units of the pool's asset, at the end of the interval
+    pub saversunits: i64,
+    pub saverearning: Option<i64>, // RUNE earned by the savers, from earning_history_nested
+    // saverEarning over the savers depth valued in RUNE, annualized. Absent when the
+    // interval's earnings or asset price are not stored.
+    pub saversapr: Option<f64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SaversQueryParams {
+    pub interval: Option<String>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub pool: Option<String>, // One or more comma-separated pools, e.g. BTC.BTC,ETH.ETH
+    pub sort_by: Option<String>,
+    pub order: Option<String>,
+    pub page: Option<i32>,
+    pub limit: Option<i32>,
+    pub count: Option<i32>,
+    pub savers_depth_gt: Option<i64>,
+    pub savers_depth_lt: Option<i64>,
+    pub savers_count_gt: Option<i64>,
+    pub savers_count_lt: Option<i64>,
+}
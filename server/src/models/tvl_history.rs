@@ -0,0 +1,35 @@
+// src/models/tvl_history.rs
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::prelude::FromRow;
+
+#[derive(Serialize, Deserialize, FromRow, Debug)]
+pub struct TvlHistory {
+    pub starttime: i64,
+    pub endtime: i64,
+    pub totalvaluepooled: i64,
+    pub totalvaluebonded: i64,
+    pub totalvaluelocked: i64,
+    pub runepriceusd: f64,
+    pub pools: Value, // [{"pool": ..., "total_depth": ...}] at the end of the interval
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TvlQueryParams {
+    pub interval: Option<String>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub sort_by: Option<String>,
+    pub order: Option<String>,
+    pub page: Option<i32>,
+    pub limit: Option<i32>,
+    pub count: Option<i32>,
+    pub total_value_pooled_gt: Option<i64>,
+    pub total_value_pooled_lt: Option<i64>,
+    pub total_value_bonded_gt: Option<i64>,
+    pub total_value_bonded_lt: Option<i64>,
+    pub total_value_locked_gt: Option<i64>,
+    pub total_value_locked_lt: Option<i64>,
+    pub rune_price_usd_gt: Option<f64>,
+    pub rune_price_usd_lt: Option<f64>,
+}
@@ -0,0 +1,83 @@
+// src/query.rs
+use crate::error::ApiError;
+use actix_web::dev::Payload;
+use actix_web::{FromRequest, HttpRequest};
+use serde::de::DeserializeOwned;
+use std::future::{ready, Ready};
+use std::ops::Deref;
+
+/// Extracts the query string like `web::Query`, but a parameter that does not deserialize
+/// is named in the problem+json `detail`, e.g. "`from`: invalid digit found in string".
+#[derive(Debug)]
+pub struct Query<T>(pub T);
+
+impl<T> Deref for Query<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned> FromRequest for Query<T> {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, ApiError>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let pairs = form_urlencoded::parse(req.query_string().as_bytes());
+        let result = serde_path_to_error::deserialize(serde_urlencoded::Deserializer::new(pairs))
+            .map(Query)
+            .map_err(|err| {
+                ApiError::validation(
+                    "invalid_query",
+                    format!("`{}`: {}", err.path(), err.inner()),
+                )
+            });
+        ready(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::runepool_history::QueryParams;
+    use actix_web::{test, web, App, HttpResponse};
+
+    async fn handler(query: Query<QueryParams>) -> HttpResponse {
+        HttpResponse::Ok().json(query.from)
+    }
+
+    #[actix_web::test]
+    async fn names_the_malformed_parameter() {
+        let app = test::init_service(App::new().route("/", web::get().to(handler))).await;
+        let request = test::TestRequest::get().uri("/?from=abc").to_request();
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), 400);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "application/problem+json"
+        );
+        let body: serde_json::Value = test::read_body_json(response).await;
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "type": "about:blank",
+                "title": "Bad Request",
+                "status": 400,
+                "detail": "`from`: invalid digit found in string",
+                "code": "invalid_query",
+            })
+        );
+    }
+
+    #[actix_web::test]
+    async fn extracts_a_valid_query() {
+        let app = test::init_service(App::new().route("/", web::get().to(handler))).await;
+        let request = test::TestRequest::get()
+            .uri("/?from=1704067200")
+            .to_request();
+        let body: Option<i64> = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(body, Some(1704067200));
+    }
+}
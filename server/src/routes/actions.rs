@@ -0,0 +1,63 @@
+use super::utils::paginate;
+use crate::error::ApiError;
+use crate::models::actions::{Action, ActionQueryParams};
+use crate::query::Query;
+use actix_web::{web, HttpResponse};
+use std::sync::Arc;
+
+/// Individual actions, newest first unless `order=asc`.
+pub async fn get_actions(
+    pool: web::Data<Arc<sqlx::PgPool>>,
+    query: Query<ActionQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    let (limit, offset) = paginate(query.page, query.limit, None);
+    let order = query.order.clone().unwrap_or_else(|| "desc".to_string());
+    let order_sql = if order == "asc" { "ASC" } else { "DESC" };
+    let types: Option<Vec<String>> = query.action_type.as_ref().map(|types| {
+        types
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect()
+    });
+    // Dates are stored in nanoseconds; bounds that overflow BIGINT once converted are
+    // rejected here rather than failing in Postgres.
+    let to_nanos = |bound: Option<i64>, name: &str| {
+        bound
+            .map(|seconds| {
+                seconds.checked_mul(1_000_000_000).ok_or_else(|| {
+                    ApiError::validation("invalid_range", format!("`{}` is out of range", name))
+                })
+            })
+            .transpose()
+    };
+    let from = to_nanos(query.from, "from")?;
+    let to = to_nanos(query.to, "to")?;
+    let query_str = format!(
+        "
+    SELECT date, height, type, status, txid, pools, in_coins, out_coins, metadata
+    FROM actions
+    WHERE ($1::TEXT IS NULL OR $1 = ANY(in_addresses) OR $1 = ANY(out_addresses))
+        AND ($2::TEXT IS NULL OR $2 = ANY(tx_ids))
+        AND ($3::TEXT[] IS NULL OR type = ANY($3))
+        AND ($4::TEXT IS NULL OR $4 = ANY(pools))
+        AND ($5::BIGINT IS NULL OR date >= $5)
+        AND ($6::BIGINT IS NULL OR date < $6)
+    ORDER BY date {}, id {}
+    LIMIT $7 OFFSET $8",
+        order_sql, order_sql
+    );
+    let rows = sqlx::query_as::<_, Action>(&query_str)
+        .bind(&query.address)
+        .bind(&query.txid)
+        .bind(&types)
+        .bind(&query.pool)
+        .bind(from)
+        .bind(to)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&***pool)
+        .await?;
+    Ok(HttpResponse::Ok().json(rows))
+}
@@ -0,0 +1,211 @@
+use crate::error::ApiError;
+
+/// How the hourly values of a column are combined into the value of a bucket.
+#[derive(Clone, Copy)]
+pub enum Rule {
+    /// Flows, e.g. counts, volumes, fees and earnings.
+    Sum,
+    /// Levels, e.g. prices, depths, units and member counts: the bucket's last value.
+    Last,
+    /// The latest value in the bucket, e.g. the end time.
+    Max,
+    /// Averages over the hour, e.g. the node count.
+    Mean,
+    /// Averages over the hour's events, e.g. slips: weighted by the named column. Buckets
+    /// whose weights are all zero fall back to the plain mean.
+    WeightedBy(&'static str),
+}
+
+/// A column of a dataset and the rule that aggregates it.
+pub struct Field {
+    pub column: &'static str,
+    pub rule: Rule,
+    /// The SQL type the aggregate is cast to, matching the field of the response model.
+    pub sql_type: &'static str,
+}
+
+impl Field {
+    pub const fn new(column: &'static str, rule: Rule, sql_type: &'static str) -> Self {
+        Field {
+            column,
+            rule,
+            sql_type,
+        }
+    }
+}
+
+/// How the hourly rows of a dataset are bucketed by `interval`. Every column the routes
+/// serve has its rule here, so a bucket of one hour is that hour's row.
+pub struct Aggregation {
+    /// The time a row is bucketed by and ordered by to find the last value.
+    pub time_column: &'static str,
+    /// Whether the rows of each pool are bucketed on their own.
+    pub per_pool: bool,
+    /// Whether the rows are hourly intervals, so an hourly bucket is a single row.
+    pub hourly: bool,
+    pub fields: &'static [Field],
+}
+
+impl Aggregation {
+    /// A SELECT over `source` with a row per bucket of `unit` (and pool): `starttime` is the
+    /// start of the bucket, followed by `pool` when per pool and then the fields.
+    pub fn select(&self, source: &str, unit: &str) -> String {
+        if unit == "hour" && self.hourly {
+            return self.select_rows(source);
+        }
+        let partition = if self.per_pool {
+            "pool, bucket"
+        } else {
+            "bucket"
+        };
+        let mut columns = vec!["bucket AS starttime".to_string()];
+        if self.per_pool {
+            columns.push("pool".to_string());
+        }
+        columns.extend(self.fields.iter().map(|field| {
+            format!(
+                "{}::{} AS {}",
+                self.expression(field),
+                field.sql_type,
+                field.column
+            )
+        }));
+        // The window aggregates the whole bucket and DISTINCT ON keeps its last row, so
+        // `Last` fields are read from that row and work for any column type. The time is
+        // qualified as `starttime` also names the bucket in the output. Buckets are
+        // truncated in UTC whatever the session's time zone.
+        format!(
+            r#"
+        SELECT DISTINCT ON ({partition})
+            {columns}
+        FROM (
+            SELECT *,
+                EXTRACT(EPOCH FROM
+                    date_trunc('{unit}', to_timestamp({time}) AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+                )::BIGINT AS bucket
+            FROM {source}
+        ) hours
+        WINDOW b AS (PARTITION BY {partition})
+        ORDER BY {partition}, hours.{time} DESC"#,
+            partition = partition,
+            columns = columns.join(",\n            "),
+            unit = unit,
+            time = self.time_column,
+            source = source,
+        )
+    }
+
+    /// The rows of `source` as they are, in the shape of [`Aggregation::select`].
+    fn select_rows(&self, source: &str) -> String {
+        let mut columns = vec![format!("{} AS starttime", self.time_column)];
+        if self.per_pool {
+            columns.push("pool".to_string());
+        }
+        columns.extend(
+            self.fields
+                .iter()
+                .map(|field| format!("{c}::{} AS {c}", field.sql_type, c = field.column)),
+        );
+        format!(
+            "
+        SELECT
+            {}
+        FROM {}",
+            columns.join(",\n            "),
+            source
+        )
+    }
+
+    fn expression(&self, field: &Field) -> String {
+        let column = field.column;
+        match field.rule {
+            Rule::Sum => format!("SUM({}) OVER b", column),
+            Rule::Last => column.to_string(),
+            Rule::Max => format!("MAX({}) OVER b", column),
+            Rule::Mean => format!("AVG({}) OVER b", column),
+            Rule::WeightedBy(weight) => format!(
+                "COALESCE(SUM({c} * {w}) OVER b / NULLIF(SUM({w}) OVER b, 0), AVG({c}) OVER b)",
+                c = column,
+                w = weight
+            ),
+        }
+    }
+}
+
+// The `interval` parameters accepted, which are also their `date_trunc` units.
+const INTERVALS: &[&str] = &["hour", "day", "week", "month", "quarter", "year"];
+
+/// The `date_trunc` unit of an `interval` parameter, hourly when it is not given, the
+/// resolution the rows are stored at, and a validation error when it is unknown.
+pub fn interval_unit(interval: Option<&str>) -> Result<&'static str, ApiError> {
+    let Some(interval) = interval else {
+        return Ok("hour");
+    };
+    match INTERVALS.iter().find(|unit| **unit == interval) {
+        Some(unit) => Ok(unit),
+        None => Err(ApiError::validation(
+            "invalid_interval",
+            format!("`{}` is not one of: {}", interval, INTERVALS.join(", ")),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+
+    const T0: i64 = 1704067200; // 2024-01-01T00:00:00Z
+
+    // Two hours of the same day: a bucket of a day holds both.
+    const HOURS: &str = "
+    WITH hourly (starttime, endtime, volume, depth, slip, swaps) AS (
+        VALUES (1704067200::BIGINT, 1704070800::BIGINT, 10, 100, 2.0, 1),
+            (1704070800, 1704074400, 30, 300, 6.0, 3)
+    )";
+
+    /// The single field of `fields` over a day of [`HOURS`].
+    async fn day_bucket(pool: &PgPool, fields: &'static [Field]) -> (i64, f64) {
+        let aggregation = Aggregation {
+            time_column: "starttime",
+            per_pool: false,
+            hourly: true,
+            fields,
+        };
+        let sql = format!("{} {}", HOURS, aggregation.select("hourly", "day"));
+        sqlx::query_as(&sql).fetch_one(pool).await.unwrap()
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn sums_flows(pool: PgPool) {
+        const FIELDS: &[Field] = &[Field::new("volume", Rule::Sum, "FLOAT8")];
+        assert_eq!(day_bucket(&pool, FIELDS).await, (T0, 40.0));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn keeps_the_last_level(pool: PgPool) {
+        const FIELDS: &[Field] = &[Field::new("depth", Rule::Last, "FLOAT8")];
+        assert_eq!(day_bucket(&pool, FIELDS).await, (T0, 300.0));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn keeps_the_latest_end_time(pool: PgPool) {
+        const FIELDS: &[Field] = &[Field::new("endtime", Rule::Max, "FLOAT8")];
+        assert_eq!(day_bucket(&pool, FIELDS).await, (T0, (T0 + 7200) as f64));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn weighs_averages_by_their_events(pool: PgPool) {
+        const FIELDS: &[Field] = &[Field::new("slip", Rule::WeightedBy("swaps"), "FLOAT8")];
+        // (2 * 1 + 6 * 3) / 4, where the plain mean would be 4.
+        assert_eq!(day_bucket(&pool, FIELDS).await, (T0, 5.0));
+    }
+
+    #[test]
+    fn rejects_unknown_intervals() {
+        assert_eq!(interval_unit(None).unwrap(), "hour");
+        assert_eq!(interval_unit(Some("week")).unwrap(), "week");
+        let err = interval_unit(Some("weak")).unwrap_err();
+        assert_eq!(err.code(), "invalid_interval");
+    }
+}
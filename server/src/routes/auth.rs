@@ -0,0 +1,53 @@
+use crate::error::ApiError;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::middleware::Next;
+use actix_web::{Error, ResponseError};
+use std::sync::OnceLock;
+
+/// The token admin requests must send as `Authorization: Bearer <token>`, from
+/// `ADMIN_TOKEN`. Without it every admin request is refused.
+fn admin_token() -> Option<&'static str> {
+    static TOKEN: OnceLock<Option<String>> = OnceLock::new();
+    TOKEN
+        .get_or_init(|| {
+            let token = std::env::var("ADMIN_TOKEN").ok().filter(|t| !t.is_empty());
+            if token.is_none() {
+                eprintln!("ADMIN_TOKEN is not set, admin routes are disabled");
+            }
+            token
+        })
+        .as_deref()
+}
+
+// Compares in constant time so the token can't be guessed byte by byte from timings.
+fn tokens_match(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len()
+        && given
+            .iter()
+            .zip(expected)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+pub async fn require_admin_token(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let given = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let authorized = match (given, admin_token()) {
+        (Some(given), Some(expected)) => tokens_match(given.as_bytes(), expected.as_bytes()),
+        _ => false,
+    };
+
+    if !authorized {
+        let response = ApiError::Unauthorized.error_response();
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+    Ok(next.call(req).await?.map_into_left_body())
+}
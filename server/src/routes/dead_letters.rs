@@ -0,0 +1,53 @@
+use super::utils::paginate;
+use crate::error::ApiError;
+use crate::models::dead_letters::{DeadLetter, DeadLetterQueryParams};
+use crate::query::Query;
+use actix_web::http::header::LOCATION;
+use actix_web::{web, HttpResponse};
+use populate::retry_dead_letter;
+use std::sync::Arc;
+
+const SELECT_DEAD_LETTERS: &str = "
+    SELECT id, dataset, pool, start_time, end_time, url, payload, error, attempts,
+        EXTRACT(EPOCH FROM first_seen_at)::BIGINT AS first_seen_at,
+        EXTRACT(EPOCH FROM last_seen_at)::BIGINT AS last_seen_at,
+        EXTRACT(EPOCH FROM resolved_at)::BIGINT AS resolved_at
+    FROM ingestion_dead_letters";
+
+pub async fn get_dead_letters(
+    pool: web::Data<Arc<sqlx::PgPool>>,
+    query: Query<DeadLetterQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    let (limit, offset) = paginate(query.page, query.limit, None);
+    let rows = sqlx::query_as::<_, DeadLetter>(&format!(
+        "{}
+    WHERE ($1::TEXT IS NULL OR dataset = $1)
+        AND ($2::TEXT IS NULL OR pool = $2)
+        AND (resolved_at IS NOT NULL) = $3
+    ORDER BY last_seen_at DESC
+    LIMIT $4 OFFSET $5",
+        SELECT_DEAD_LETTERS
+    ))
+    .bind(&query.dataset)
+    .bind(&query.pool)
+    .bind(query.resolved.unwrap_or(false))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&***pool)
+    .await?;
+    Ok(HttpResponse::Ok().json(rows))
+}
+
+/// Queues a run re-fetching the dead letter's interval. Responds with the run id to poll
+/// at `/admin/ingestion/runs/{id}`; the dead letter's `resolved_at` is set once the run
+/// ingested the interval.
+pub async fn post_retry_dead_letter(
+    pool: web::Data<Arc<sqlx::PgPool>>,
+    id: web::Path<i64>,
+) -> Result<HttpResponse, ApiError> {
+    let id = id.into_inner();
+    let run_id = retry_dead_letter(&pool, id).await?;
+    Ok(HttpResponse::Accepted()
+        .insert_header((LOCATION, format!("/admin/ingestion/runs/{}", run_id)))
+        .json(serde_json::json!({"id": run_id, "dead_letter": id, "status": "queued"})))
+}
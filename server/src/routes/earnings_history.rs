@@ -1,183 +1,144 @@
-use super::utils::{add_condition, paginate};
+use super::aggregation::{interval_unit, Aggregation, Field, Rule};
+use super::utils::{paginate, sort_column, FilteredQuery};
+use crate::error::ApiError;
 use crate::models::earnings_history::{EarningHistoryQueryParams, EarningHistoryResponse};
-use actix_web::{web, HttpResponse, Responder};
-use sqlx::{Pool, Postgres};
+use crate::query::Query;
+use actix_web::{web, HttpResponse};
 use std::sync::Arc;
 
+const SORT_COLUMNS: &[&str] = &[
+    "starttime",
+    "endtime",
+    "avgnodecount",
+    "blockrewards",
+    "bondingearnings",
+    "earnings",
+    "liquidityearnings",
+    "liquidityfees",
+    "runepriceusd",
+];
+
+/// Rewards, earnings and fees add up; the node count is averaged over the hours.
+const AGGREGATION: Aggregation = Aggregation {
+    time_column: "starttime",
+    per_pool: false,
+    hourly: true,
+    fields: &[
+        Field::new("endtime", Rule::Max, "BIGINT"),
+        Field::new("avgnodecount", Rule::Mean, "FLOAT8"),
+        Field::new("blockrewards", Rule::Sum, "BIGINT"),
+        Field::new("bondingearnings", Rule::Sum, "BIGINT"),
+        Field::new("earnings", Rule::Sum, "BIGINT"),
+        Field::new("liquidityearnings", Rule::Sum, "BIGINT"),
+        Field::new("liquidityfees", Rule::Sum, "BIGINT"),
+        Field::new("runepriceusd", Rule::Last, "FLOAT8"),
+    ],
+};
+
+/// The earnings of each pool, reported in `pools`, add up as well.
+const POOL_AGGREGATION: Aggregation = Aggregation {
+    time_column: "starttime",
+    per_pool: true,
+    hourly: true,
+    fields: &[
+        Field::new("assetliquidityfees", Rule::Sum, "BIGINT"),
+        Field::new("earnings", Rule::Sum, "BIGINT"),
+        Field::new("rewards", Rule::Sum, "BIGINT"),
+        Field::new("runeliquidityfees", Rule::Sum, "BIGINT"),
+        Field::new("saverearning", Rule::Sum, "BIGINT"),
+        Field::new("totalliquidityfeesrune", Rule::Sum, "BIGINT"),
+    ],
+};
+
 pub async fn get_earning_history(
     pool: web::Data<Arc<sqlx::PgPool>>,
-    query: web::Query<EarningHistoryQueryParams>,
-) -> impl Responder {
-    println!("Getting earning history");
-    let query_str = build_earning_history_query(&query);
-    println!("Generated query: {}", query_str);
-    let rows = sqlx::query_as::<_, EarningHistoryResponse>(&query_str)
+    query: Query<EarningHistoryQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    let mut query_builder = build_earning_history_query(&query)?;
+    let rows = query_builder
+        .build_query_as::<EarningHistoryResponse>()
         .fetch_all(&***pool)
-        .await;
-
-    match rows {
-        Ok(deserialized_rows) => HttpResponse::Ok().json(deserialized_rows),
-        Err(err) => HttpResponse::InternalServerError().json({
-            serde_json::json!({"error": "Error fetching data", "details": err.to_string()})
-        }),
-    }
+        .await?;
+    Ok(HttpResponse::Ok().json(rows))
 }
 
-fn build_earning_history_query(query: &EarningHistoryQueryParams) -> String {
-    let mut where_clauses = vec![];
-
-    // Time range filters
-    if let Some(from) = query.from {
-        where_clauses.push(format!("starttime >= {}", from));
-    }
-    if let Some(to) = query.to {
-        where_clauses.push(format!("endtime <= {}", to));
-    }
-
-    // Dynamic filters for earning_history fields
-    add_condition(
-        &mut where_clauses,
-        "avgnodecount",
-        &query.avg_node_count_gt,
-        ">",
-    );
-    add_condition(
-        &mut where_clauses,
-        "avgnodecount",
-        &query.avg_node_count_lt,
-        "<",
-    );
-    add_condition(
-        &mut where_clauses,
-        "blockrewards",
-        &query.block_rewards_gt,
-        ">",
-    );
-    add_condition(
-        &mut where_clauses,
-        "blockrewards",
-        &query.block_rewards_lt,
-        "<",
-    );
-    add_condition(&mut where_clauses, "earnings", &query.earnings_gt, ">");
-    add_condition(&mut where_clauses, "earnings", &query.earnings_lt, "<");
-    add_condition(
-        &mut where_clauses,
-        "liquidityearnings",
-        &query.liquidity_earnings_gt,
-        ">",
-    );
-    add_condition(
-        &mut where_clauses,
-        "liquidityearnings",
-        &query.liquidity_earnings_lt,
-        "<",
-    );
-    add_condition(
-        &mut where_clauses,
-        "runepriceusd",
-        &query.rune_price_usd_gt,
-        ">",
-    );
-    add_condition(
-        &mut where_clauses,
-        "runepriceusd",
-        &query.rune_price_usd_lt,
-        "<",
-    );
-    add_condition(
-        &mut where_clauses,
-        "runepriceusd",
-        &query.rune_price_usd_eq,
-        "=",
-    );
-
-    let where_sql = if where_clauses.is_empty() {
-        "TRUE".to_string()
-    } else {
-        where_clauses.join(" AND ")
-    };
-
+fn build_earning_history_query(
+    query: &EarningHistoryQueryParams,
+) -> Result<FilteredQuery, ApiError> {
     // Sorting and pagination
-    let sort_by = query
-        .sort_by
-        .clone()
-        .unwrap_or_else(|| "starttime".to_string());
+    let sort_by = sort_column(&query.sort_by, SORT_COLUMNS, "starttime")?;
     let order = query.order.clone().unwrap_or_else(|| "asc".to_string());
     let order_sql = if order == "desc" { "DESC" } else { "ASC" };
     let hard_limit = query.count.unwrap_or(400).min(400);
     let (pagination_limit, offset) = paginate(query.page, query.limit, query.count);
     let effective_limit = hard_limit.min(pagination_limit);
-    let interval_seconds = match query.interval.as_deref() {
-        Some("day") => 86400,     // 24 * 60 * 60
-        Some("week") => 604800,   // 7 * 24 * 60 * 60
-        Some("month") => 2592000, // Approximate 30 days
-        _ => 3600,                // Default to hourly (1 hour = 60 * 60)
-    };
-    // Final SQL Query
-    format!(
+    let unit = interval_unit(query.interval.as_deref())?;
+    let mut query_builder = FilteredQuery::new(
         r#"
-    WITH grouped_data AS (
-        SELECT
-            (starttime / {interval_seconds}) * {interval_seconds} AS bracket_start,
-            eh.avgnodecount ::FLOAT8 AS avgnodecount,
-            eh.blockrewards,
-            eh.bondingearnings,
-            eh.earnings,
-            eh.endtime,
-            eh.liquidityearnings,
-            eh.liquidityfees,
-            eh.runepriceusd,
-            eh.starttime,
-            eh.pools,
-            ROW_NUMBER() OVER (
-                PARTITION BY (starttime / {interval_seconds})
-                ORDER BY {sort_by} DESC
-            ) AS rank,
-            (
-                SELECT array_agg(
-                    jsonb_build_object(
-                        'pool', en.pool,
-                        'asset_liquidity_fees', en.assetliquidityfees,
-                        'earnings', en.earnings,
-                        'rewards', en.rewards,
-                        'rune_liquidity_fees', en.runeliquidityfees,
-                        'saver_earning', en.saverearning,
-                        'total_liquidity_fees_rune', en.totalliquidityfeesrune
-                    )
-                )
-                FROM earning_history_nested en
-                WHERE en.id = ANY(eh.pools)
-            ) AS nested_pools
-        FROM earning_history eh
-        WHERE {where_sql}
+    WITH hourly AS (
+        SELECT *
+        FROM earning_history
+        WHERE"#,
+    );
+
+    // Time range filters
+    query_builder.condition("starttime", ">=", query.from);
+    query_builder.condition("endtime", "<=", query.to);
+
+    // Dynamic filters for earning_history fields
+    query_builder.condition("avgnodecount", ">", query.avg_node_count_gt);
+    query_builder.condition("avgnodecount", "<", query.avg_node_count_lt);
+    query_builder.condition("blockrewards", ">", query.block_rewards_gt);
+    query_builder.condition("blockrewards", "<", query.block_rewards_lt);
+    query_builder.condition("earnings", ">", query.earnings_gt);
+    query_builder.condition("earnings", "<", query.earnings_lt);
+    query_builder.condition("liquidityearnings", ">", query.liquidity_earnings_gt);
+    query_builder.condition("liquidityearnings", "<", query.liquidity_earnings_lt);
+    query_builder.condition("runepriceusd", ">", query.rune_price_usd_gt);
+    query_builder.condition("runepriceusd", "<", query.rune_price_usd_lt);
+    query_builder.condition("runepriceusd", "=", query.rune_price_usd_eq);
+
+    query_builder.push(format!(
+        r#"
+    ),
+    hourly_pools AS (
+        SELECT h.starttime, en.*
+        FROM hourly h
+        JOIN earning_history_nested en ON en.earning_history_id = h.id
+    ),
+    pool_buckets AS ({pool_buckets}
     )
     SELECT
-        MIN(bracket_start) AS starttime,
-        MAX(starttime) AS endtime,
-        MIN(starttime) AS first_starttime,
-        MAX(endtime) AS last_endtime,
-        avgnodecount,
-        blockrewards,
-        bondingearnings,
-        earnings,
-        liquidityearnings,
-        liquidityfees,
-        runepriceusd,
-        nested_pools AS pools
-    FROM grouped_data
-    WHERE rank = 1
-    GROUP BY 
-        avgnodecount, blockrewards, bondingearnings, earnings, 
-        liquidityearnings, liquidityfees, runepriceusd, nested_pools
+        b.*,
+        COALESCE((
+            SELECT array_agg(
+                jsonb_build_object(
+                    'pool', p.pool,
+                    'asset_liquidity_fees', p.assetliquidityfees,
+                    'earnings', p.earnings,
+                    'rewards', p.rewards,
+                    'rune_liquidity_fees', p.runeliquidityfees,
+                    'saver_earning', p.saverearning,
+                    'total_liquidity_fees_rune', p.totalliquidityfeesrune
+                )
+                ORDER BY p.pool
+            )
+            FROM pool_buckets p
+            WHERE p.starttime = b.starttime
+        ), '{{}}') AS pools
+    FROM ({buckets}
+    ) b
     ORDER BY {sort_by} {order_sql}
-    LIMIT {limit} OFFSET {offset}
-    "#,
-        interval_seconds = interval_seconds,
-        where_sql = where_sql,
+"#,
+        pool_buckets = POOL_AGGREGATION.select("hourly_pools", unit),
+        buckets = AGGREGATION.select("hourly", unit),
         sort_by = sort_by,
-        order_sql = order_sql,
-        limit = effective_limit,
-        offset = offset
-    )
+        order_sql = order_sql
+    ));
+    query_builder
+        .push(" LIMIT ")
+        .push_bind(effective_limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    Ok(query_builder)
 }
@@ -0,0 +1,77 @@
+use super::utils::validate_pools;
+use crate::error::ApiError;
+use crate::models::ingestion_runs::IngestRequest;
+use crate::query::Query;
+use actix_web::http::header::LOCATION;
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use populate::scripts::runs::{enqueue_run, RunKind};
+use populate::Dataset;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Queues a run of `dataset` for the scheduler: a backfill of `[from, to)` when `from` is
+/// given, limited to `pool` when that is given too, otherwise a sync from the checkpoint.
+/// Responds with the run id to poll at
+/// `/admin/ingestion/runs/{id}`.
+pub async fn post_ingest(
+    pool: web::Data<Arc<sqlx::PgPool>>,
+    dataset: web::Path<String>,
+    query: Query<IngestRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let dataset =
+        Dataset::from_str(&dataset).map_err(|err| ApiError::not_found("unknown_dataset", err))?;
+    let range = match (query.from, query.to) {
+        (Some(from), to) => {
+            let to = to.unwrap_or_else(|| Utc::now().timestamp());
+            if from >= to {
+                return Err(ApiError::validation(
+                    "invalid_range",
+                    "`from` must be before `to`",
+                ));
+            }
+            Some((from, to))
+        }
+        (None, Some(_)) => {
+            return Err(ApiError::validation(
+                "invalid_range",
+                "`to` requires `from`",
+            ))
+        }
+        (None, None) => None,
+    };
+    if query.pool.is_some() && !dataset.is_per_pool() {
+        return Err(ApiError::validation(
+            "not_per_pool",
+            format!("{} is not ingested per pool", dataset),
+        ));
+    }
+    if query.pool.as_deref().is_some_and(|pool| pool.contains(',')) {
+        return Err(ApiError::validation(
+            "invalid_pool",
+            "`pool` takes a single pool; queue one run per pool",
+        ));
+    }
+    if query.pool.is_some() && range.is_none() {
+        return Err(ApiError::validation(
+            "invalid_range",
+            "`pool` requires `from`: a sync covers every pool",
+        ));
+    }
+    if range.is_some() && !dataset.has_history() {
+        return Err(ApiError::validation(
+            "no_history",
+            format!("{} has no history to backfill", dataset),
+        ));
+    }
+    validate_pools(&pool, &query.pool).await?;
+
+    let kind = match range {
+        Some(_) => RunKind::Backfill,
+        None => RunKind::Sync,
+    };
+    let id = enqueue_run(&pool, dataset, kind, query.pool.as_deref(), range).await?;
+    Ok(HttpResponse::Accepted()
+        .insert_header((LOCATION, format!("/admin/ingestion/runs/{}", id)))
+        .json(serde_json::json!({"id": id, "dataset": dataset.table(), "status": "queued"})))
+}
@@ -0,0 +1,64 @@
+use super::utils::paginate;
+use crate::error::ApiError;
+use crate::models::ingestion_runs::{IngestionRun, IngestionRunQueryParams};
+use crate::query::Query;
+use actix_web::{web, HttpResponse};
+use std::sync::Arc;
+
+const RUN_COLUMNS: &str = "
+    id, dataset, kind, pool, range_from, range_to, status,
+    EXTRACT(EPOCH FROM queued_at)::BIGINT AS queued_at,
+    EXTRACT(EPOCH FROM started_at)::BIGINT AS started_at,
+    EXTRACT(EPOCH FROM finished_at)::BIGINT AS finished_at,
+    pages_fetched, rows_inserted, rows_updated, rows_skipped, rows_rejected, error";
+
+pub async fn get_ingestion_runs(
+    pool: web::Data<Arc<sqlx::PgPool>>,
+    query: Query<IngestionRunQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    let (limit, offset) = paginate(query.page, query.limit, None);
+    // With `latest`, DISTINCT ON keeps the newest matching run of each dataset.
+    let (distinct, distinct_order) = if query.latest.unwrap_or(false) {
+        ("DISTINCT ON (dataset)", "dataset,")
+    } else {
+        ("", "")
+    };
+    let query_str = format!(
+        "
+    SELECT * FROM (
+        SELECT {} {}
+        FROM ingestion_runs
+        WHERE ($1::TEXT IS NULL OR dataset = $1)
+            AND ($2::TEXT IS NULL OR kind = $2)
+            AND ($3::TEXT IS NULL OR status = $3)
+        ORDER BY {} started_at DESC, id DESC
+    ) runs
+    ORDER BY started_at DESC, id DESC
+    LIMIT $4 OFFSET $5",
+        distinct, RUN_COLUMNS, distinct_order
+    );
+    let rows = sqlx::query_as::<_, IngestionRun>(&query_str)
+        .bind(&query.dataset)
+        .bind(&query.kind)
+        .bind(&query.status)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&***pool)
+        .await?;
+    Ok(HttpResponse::Ok().json(rows))
+}
+
+pub async fn get_ingestion_run(
+    pool: web::Data<Arc<sqlx::PgPool>>,
+    id: web::Path<i64>,
+) -> Result<HttpResponse, ApiError> {
+    let query_str = format!("SELECT {} FROM ingestion_runs WHERE id = $1", RUN_COLUMNS);
+    let row = sqlx::query_as::<_, IngestionRun>(&query_str)
+        .bind(id.into_inner())
+        .fetch_optional(&***pool)
+        .await?;
+    match row {
+        Some(row) => Ok(HttpResponse::Ok().json(row)),
+        None => Err(ApiError::not_found("run_not_found", "Run not found")),
+    }
+}
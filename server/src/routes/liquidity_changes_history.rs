@@ -0,0 +1,148 @@
+use super::aggregation::{interval_unit, Aggregation, Field, Rule};
+use super::utils::{paginate, sort_column, validate_pools, FilteredQuery};
+use crate::error::ApiError;
+use crate::models::liquidity_changes_history::{
+    LiquidityChangesHistory, LiquidityChangesQueryParams,
+};
+use crate::query::Query;
+use actix_web::{web, HttpResponse};
+use std::sync::Arc;
+
+const SORT_COLUMNS: &[&str] = &[
+    "pool",
+    "starttime",
+    "endtime",
+    "addassetliquidityvolume",
+    "addruneliquidityvolume",
+    "addliquidityvolume",
+    "addliquidityvolumeusd",
+    "addliquiditycount",
+    "removeassetliquidityvolume",
+    "removeruneliquidityvolume",
+    "removeliquidityvolume",
+    "removeliquidityvolumeusd",
+    "removeliquiditycount",
+    "netchange",
+    "netchangeusd",
+    "runepriceusd",
+];
+
+/// Liquidity changes are flows: a bucket adds up its hours, except the RUNE price which is
+/// taken from its last hour.
+const AGGREGATION: Aggregation = Aggregation {
+    time_column: "starttime",
+    per_pool: true,
+    hourly: true,
+    fields: &[
+        Field::new("endtime", Rule::Max, "BIGINT"),
+        Field::new("addassetliquidityvolume", Rule::Sum, "BIGINT"),
+        Field::new("addruneliquidityvolume", Rule::Sum, "BIGINT"),
+        Field::new("addliquidityvolume", Rule::Sum, "BIGINT"),
+        Field::new("addliquidityvolumeusd", Rule::Sum, "FLOAT8"),
+        Field::new("addliquiditycount", Rule::Sum, "BIGINT"),
+        Field::new("removeassetliquidityvolume", Rule::Sum, "BIGINT"),
+        Field::new("removeruneliquidityvolume", Rule::Sum, "BIGINT"),
+        Field::new("removeliquidityvolume", Rule::Sum, "BIGINT"),
+        Field::new("removeliquidityvolumeusd", Rule::Sum, "FLOAT8"),
+        Field::new("removeliquiditycount", Rule::Sum, "BIGINT"),
+        Field::new("netchange", Rule::Sum, "BIGINT"),
+        Field::new("netchangeusd", Rule::Sum, "FLOAT8"),
+        Field::new("runepriceusd", Rule::Last, "FLOAT8"),
+    ],
+};
+
+pub async fn get_liquidity_changes_history(
+    pool: web::Data<Arc<sqlx::PgPool>>,
+    query: Query<LiquidityChangesQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    validate_pools(&pool, &query.pool).await?;
+    let mut query_builder = build_query(&query)?;
+    let rows = query_builder
+        .build_query_as::<LiquidityChangesHistory>()
+        .fetch_all(&***pool)
+        .await?;
+    Ok(HttpResponse::Ok().json(rows))
+}
+
+fn build_query(query: &LiquidityChangesQueryParams) -> Result<FilteredQuery, ApiError> {
+    // Sorting and pagination
+    let sort_by = sort_column(&query.sort_by, SORT_COLUMNS, "starttime")?;
+    let order = query.order.clone().unwrap_or_else(|| "asc".to_string());
+    let order_sql = if order == "desc" { "DESC" } else { "ASC" };
+    let hard_limit = query.count.unwrap_or(400).min(400);
+    let (pagination_limit, offset) = paginate(query.page, query.limit, query.count);
+    let effective_limit = hard_limit.min(pagination_limit);
+
+    let mut query_builder = FilteredQuery::new(
+        r#"
+    WITH hourly AS (
+        SELECT
+            NULLIF(pool, '') AS pool,
+            starttime,
+            endtime,
+            addassetliquidityvolume,
+            addruneliquidityvolume,
+            addliquidityvolume,
+            addliquidityvolumeusd,
+            addliquiditycount,
+            removeassetliquidityvolume,
+            removeruneliquidityvolume,
+            removeliquidityvolume,
+            removeliquidityvolumeusd,
+            removeliquiditycount,
+            netchange,
+            netchangeusd,
+            runepriceusd
+        FROM liquidity_changes_history
+        WHERE"#,
+    );
+
+    // Time range filters
+    query_builder.condition("starttime", ">=", query.from);
+    query_builder.condition("endtime", "<=", query.to);
+
+    // The network-wide totals are stored under the empty pool name.
+    if query.pool.is_some() {
+        query_builder.in_condition("pool", &query.pool);
+    } else {
+        query_builder.raw_condition("pool = ''");
+    }
+
+    // Filters apply to the hourly rows, before they are aggregated
+    query_builder.condition("addliquiditycount", ">", query.add_liquidity_count_gt);
+    query_builder.condition("addliquiditycount", "<", query.add_liquidity_count_lt);
+    query_builder.condition("removeliquiditycount", ">", query.remove_liquidity_count_gt);
+    query_builder.condition("removeliquiditycount", "<", query.remove_liquidity_count_lt);
+    query_builder.condition("addliquidityvolume", ">", query.add_liquidity_volume_gt);
+    query_builder.condition("addliquidityvolume", "<", query.add_liquidity_volume_lt);
+    query_builder.condition(
+        "removeliquidityvolume",
+        ">",
+        query.remove_liquidity_volume_gt,
+    );
+    query_builder.condition(
+        "removeliquidityvolume",
+        "<",
+        query.remove_liquidity_volume_lt,
+    );
+    query_builder.condition("netchange", ">", query.net_change_gt);
+    query_builder.condition("netchange", "<", query.net_change_lt);
+
+    query_builder.push(format!(
+        r#"
+    )
+    SELECT * FROM ({buckets}
+    ) buckets
+    ORDER BY {sort_by} {order_sql}, pool
+    "#,
+        buckets = AGGREGATION.select("hourly", interval_unit(query.interval.as_deref())?),
+        sort_by = sort_by,
+        order_sql = order_sql,
+    ));
+    query_builder
+        .push(" LIMIT ")
+        .push_bind(effective_limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    Ok(query_builder)
+}
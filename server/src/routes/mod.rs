@@ -1,5 +1,17 @@
+pub mod actions;
+pub mod aggregation;
 pub mod runepool_history;
 pub mod depth_price_history;
 pub mod swaps_history;
 pub mod earnings_history;
+pub mod liquidity_changes_history;
+pub mod network_history;
+pub mod pools;
+pub mod savers_history;
+pub mod tvl_history;
+pub mod schedule;
+pub mod dead_letters;
+pub mod ingestion_runs;
+pub mod ingest;
+pub mod auth;
 pub mod utils;
\ No newline at end of file
@@ -0,0 +1,101 @@
+use super::aggregation::{interval_unit, Aggregation, Field, Rule};
+use super::utils::{paginate, sort_column, FilteredQuery};
+use crate::error::ApiError;
+use crate::models::network_history::{NetworkHistory, NetworkQueryParams};
+use crate::query::Query;
+use actix_web::{web, HttpResponse};
+use std::sync::Arc;
+
+const SORT_COLUMNS: &[&str] = &[
+    "starttime",
+    "snapshottime",
+    "bondingapy",
+    "liquidityapy",
+    "totalreserve",
+    "totalpooledrune",
+    "activenodecount",
+    "standbynodecount",
+    "activebonds",
+    "totalactivebond",
+    "totalstandbybond",
+    "nextchurnheight",
+];
+
+/// Snapshots are states: a bucket reports its last snapshot.
+const AGGREGATION: Aggregation = Aggregation {
+    time_column: "snapshottime",
+    per_pool: false,
+    hourly: false,
+    fields: &[
+        Field::new("snapshottime", Rule::Last, "BIGINT"),
+        Field::new("bondingapy", Rule::Last, "FLOAT8"),
+        Field::new("liquidityapy", Rule::Last, "FLOAT8"),
+        Field::new("totalreserve", Rule::Last, "BIGINT"),
+        Field::new("totalpooledrune", Rule::Last, "BIGINT"),
+        Field::new("activenodecount", Rule::Last, "INT"),
+        Field::new("standbynodecount", Rule::Last, "INT"),
+        Field::new("activebonds", Rule::Last, "BIGINT[]"),
+        Field::new("totalactivebond", Rule::Last, "BIGINT"),
+        Field::new("totalstandbybond", Rule::Last, "BIGINT"),
+        Field::new("nextchurnheight", Rule::Last, "BIGINT"),
+    ],
+};
+
+pub async fn get_network_history(
+    pool: web::Data<Arc<sqlx::PgPool>>,
+    query: Query<NetworkQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    let mut query_builder = build_query(&query)?;
+    let rows = query_builder
+        .build_query_as::<NetworkHistory>()
+        .fetch_all(&***pool)
+        .await?;
+    Ok(HttpResponse::Ok().json(rows))
+}
+
+fn build_query(query: &NetworkQueryParams) -> Result<FilteredQuery, ApiError> {
+    // Sorting and pagination
+    let sort_by = sort_column(&query.sort_by, SORT_COLUMNS, "starttime")?;
+    let order = query.order.clone().unwrap_or_else(|| "asc".to_string());
+    let order_sql = if order == "desc" { "DESC" } else { "ASC" };
+    let hard_limit = query.count.unwrap_or(400).min(400);
+    let (pagination_limit, offset) = paginate(query.page, query.limit, query.count);
+    let effective_limit = hard_limit.min(pagination_limit);
+
+    let mut query_builder = FilteredQuery::new(
+        r#"
+    WITH snapshots AS (
+        SELECT *
+        FROM network_snapshots
+        WHERE"#,
+    );
+
+    // Time range filters
+    query_builder.condition("snapshottime", ">=", query.from);
+    query_builder.condition("snapshottime", "<", query.to);
+
+    query_builder.condition("bondingapy", ">", query.bonding_apy_gt);
+    query_builder.condition("bondingapy", "<", query.bonding_apy_lt);
+    query_builder.condition("liquidityapy", ">", query.liquidity_apy_gt);
+    query_builder.condition("liquidityapy", "<", query.liquidity_apy_lt);
+    query_builder.condition("activenodecount", ">", query.active_node_count_gt);
+    query_builder.condition("activenodecount", "<", query.active_node_count_lt);
+
+    query_builder.push(format!(
+        r#"
+    )
+    SELECT * FROM ({buckets}
+    ) buckets
+    ORDER BY {sort_by} {order_sql}
+    "#,
+        buckets = AGGREGATION.select("snapshots", interval_unit(query.interval.as_deref())?),
+        sort_by = sort_by,
+        order_sql = order_sql,
+    ));
+    query_builder
+        .push(" LIMIT ")
+        .push_bind(effective_limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    Ok(query_builder)
+}
@@ -0,0 +1,103 @@
+use super::utils::{paginate, sort_column, FilteredQuery};
+use crate::error::ApiError;
+use crate::models::pools::{
+    Pool, PoolQueryParams, PoolSnapshot, PoolSnapshotQueryParams, PoolWithSnapshots,
+};
+use crate::query::Query;
+use actix_web::{web, HttpResponse};
+use std::sync::Arc;
+
+const POOL_COLUMNS: &str = "
+    asset, status, nativedecimal, assetdepth, runedepth, assetprice, assetpriceusd, units,
+    liquidityunits, synthunits, synthsupply, volume24h, annualpercentagerate, poolapy,
+    saversdepth, saversunits, saversapr, firstseen, lastseen";
+
+const SORT_COLUMNS: &[&str] = &[
+    "asset",
+    "status",
+    "nativedecimal",
+    "assetdepth",
+    "runedepth",
+    "assetprice",
+    "assetpriceusd",
+    "units",
+    "liquidityunits",
+    "synthunits",
+    "synthsupply",
+    "volume24h",
+    "annualpercentagerate",
+    "poolapy",
+    "saversdepth",
+    "saversunits",
+    "saversapr",
+    "firstseen",
+    "lastseen",
+];
+
+/// The latest snapshot of every pool Midgard has listed.
+pub async fn get_pools(
+    pool: web::Data<Arc<sqlx::PgPool>>,
+    query: Query<PoolQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    let sort_by = sort_column(&query.sort_by, SORT_COLUMNS, "asset")?;
+    let order = query.order.clone().unwrap_or_else(|| "asc".to_string());
+    let order_sql = if order == "desc" { "DESC" } else { "ASC" };
+    let (limit, offset) = paginate(query.page, query.limit, None);
+    let mut query_builder = FilteredQuery::new(format!("SELECT {} FROM pools WHERE", POOL_COLUMNS));
+    query_builder.in_condition("status", &query.status);
+    query_builder
+        .push(format!(" ORDER BY {} {}, asset LIMIT ", sort_by, order_sql))
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    let rows = query_builder
+        .build_query_as::<Pool>()
+        .fetch_all(&***pool)
+        .await?;
+    Ok(HttpResponse::Ok().json(rows))
+}
+
+/// One pool's latest state along with its snapshot history.
+pub async fn get_pool(
+    pool: web::Data<Arc<sqlx::PgPool>>,
+    asset: web::Path<String>,
+    query: Query<PoolSnapshotQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    let asset = asset.into_inner();
+    let query_str = format!("SELECT {} FROM pools WHERE asset = $1", POOL_COLUMNS);
+    let row = sqlx::query_as::<_, Pool>(&query_str)
+        .bind(&asset)
+        .fetch_optional(&***pool)
+        .await?
+        .ok_or_else(|| {
+            ApiError::not_found("pool_not_found", format!("Pool {} not found", asset))
+        })?;
+
+    let order = query.order.clone().unwrap_or_else(|| "desc".to_string());
+    let order_sql = if order == "asc" { "ASC" } else { "DESC" };
+    let (limit, offset) = paginate(query.page, query.limit, None);
+    let query_str = format!(
+        "
+    SELECT snapshottime, status, assetdepth, runedepth, assetprice, assetpriceusd, units,
+        liquidityunits, volume24h, annualpercentagerate, poolapy, saversdepth, saversapr
+    FROM pool_snapshots
+    WHERE asset = $1
+        AND ($2::BIGINT IS NULL OR snapshottime >= $2)
+        AND ($3::BIGINT IS NULL OR snapshottime < $3)
+    ORDER BY snapshottime {}
+    LIMIT $4 OFFSET $5",
+        order_sql
+    );
+    let snapshots = sqlx::query_as::<_, PoolSnapshot>(&query_str)
+        .bind(&asset)
+        .bind(query.from)
+        .bind(query.to)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&***pool)
+        .await?;
+    Ok(HttpResponse::Ok().json(PoolWithSnapshots {
+        pool: row,
+        snapshots,
+    }))
+}
@@ -1,151 +1,85 @@
-use super::utils::{paginate, add_condition};
+use super::aggregation::{interval_unit, Aggregation, Field, Rule};
+use super::utils::{paginate, sort_column, FilteredQuery};
+use crate::error::ApiError;
 use crate::models::runepool_history::{QueryParams, RunepoolHistory};
-use actix_web::{web, HttpResponse, Responder};
+use crate::query::Query;
+use actix_web::{web, HttpResponse};
 use std::sync::Arc;
 
+const SORT_COLUMNS: &[&str] = &["starttime", "endtime", "units", "count"];
+
+/// Units and member count are levels: a bucket reports its last hour.
+const AGGREGATION: Aggregation = Aggregation {
+    time_column: "starttime",
+    per_pool: false,
+    hourly: true,
+    fields: &[
+        Field::new("endtime", Rule::Max, "BIGINT"),
+        Field::new("units", Rule::Last, "BIGINT"),
+        Field::new("count", Rule::Last, "INT"),
+    ],
+};
+
 pub async fn get_runepool_history(
     pool: web::Data<Arc<sqlx::PgPool>>,
-    query: web::Query<QueryParams>,
-) -> impl Responder {
-    println!("getting_runepool_history");
-    let query_str = build_query(&query);
-    println!("connecting: {}", query_str);
-    let rows = sqlx::query_as::<_, RunepoolHistory>(&query_str)
+    query: Query<QueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    let mut query_builder = build_query(&query)?;
+    let rows = query_builder
+        .build_query_as::<RunepoolHistory>()
         .fetch_all(&***pool)
-        .await;
-    match rows {
-        Ok(rows) => HttpResponse::Ok().json(rows),
-        Err(err) => HttpResponse::InternalServerError().json({
-            serde_json::json!({"error": "Error fetching data", "details": err.to_string()})
-        }),
-    }
+        .await?;
+    Ok(HttpResponse::Ok().json(rows))
 }
 
-fn build_query(query: &QueryParams) -> String {
-    println!("{:?}", query);
-    let mut where_clauses = vec![];
-
+fn add_conditions(query_builder: &mut FilteredQuery, query: &QueryParams) {
     // Time range filters
-    if let Some(from) = query.from {
-        where_clauses.push(format!("starttime >= {}", from));
-    }
-
-    if let Some(to) = query.to {
-        where_clauses.push(format!("endtime <= {}", to));
-    }
+    query_builder.condition("starttime", ">=", query.from);
+    query_builder.condition("endtime", "<=", query.to);
 
     // Filters for `units`
-    if let Some(units_gt) = query.units_gt {
-        where_clauses.push(format!("units > {}", units_gt));
-    }
-
-    if let Some(units_lt) = query.units_lt {
-        where_clauses.push(format!("units < {}", units_lt));
-    }
-
-    if let Some(units_eq) = query.units_eq {
-        where_clauses.push(format!("units = {}", units_eq));
-    }
+    query_builder.condition("units", ">", query.units_gt);
+    query_builder.condition("units", "<", query.units_lt);
+    query_builder.condition("units", "=", query.units_eq);
 
     // Filters for `count`
-    if let Some(count_gt) = query.count_gt {
-        where_clauses.push(format!("count > {}", count_gt));
-    }
-
-    if let Some(count_lt) = query.count_lt {
-        where_clauses.push(format!("count < {}", count_lt));
-    }
-
-    if let Some(count_eq) = query.count_eq {
-        where_clauses.push(format!("count = {}", count_eq));
-    }
-
-    // Combining all WHERE clauses
-    let where_sql = if where_clauses.is_empty() {
-        "TRUE".to_string()
-    } else {
-        where_clauses.join(" AND ")
-    };
-
-    println!("WHERE clause: {}", where_sql);
+    query_builder.condition("count", ">", query.count_gt);
+    query_builder.condition("count", "<", query.count_lt);
+    query_builder.condition("count", "=", query.count_eq);
+}
 
+fn build_query(query: &QueryParams) -> Result<FilteredQuery, ApiError> {
     // Sorting and ordering logic
-    let sort_by = query
-        .sort_by
-        .clone()
-        .unwrap_or_else(|| "starttime".to_string());
+    let sort_by = sort_column(&query.sort_by, SORT_COLUMNS, "starttime")?;
     let order = query.order.clone().unwrap_or_else(|| "asc".to_string());
     let order_sql = if order == "desc" { "DESC" } else { "ASC" };
 
     let hard_limit = query.count.unwrap_or(400).min(400);
     let (pagination_limit, offset) = paginate(query.page, query.limit, query.count);
     let effective_limit = hard_limit.min(pagination_limit);
-    // Handling interval
-    if let Some(interval) = &query.interval {
-        let interval_sql = match interval.as_str() {
-            "hour" => "hour",
-            "day" => "day",
-            "week" => "week",
-            "month" => "month",
-            "quarter" => "quarter",
-            "year" => "year",
-            _ => "hour", // Default to hourly if an invalid interval is provided
-        };
-
-        if interval == "hour" {
-            // Directly query hourly data
-            format!(
-                r#"
-                SELECT starttime, endtime, units, count
-                FROM runepool_history
-                WHERE {}
-                ORDER BY starttime {}
-                LIMIT {}
-                OFFSET {}
-                "#,
-                where_sql, order_sql, effective_limit, offset
-            )
-        } else {
-            // Aggregate for larger intervals
-            format!(
-                r#"
-        WITH grouped_data AS (
-            SELECT
-                EXTRACT(EPOCH FROM date_trunc('{}', to_timestamp(starttime)))::BIGINT AS bracket_start,
-                starttime, endtime, units, count,
-                ROW_NUMBER() OVER (
-                    PARTITION BY date_trunc('{}', to_timestamp(starttime))
-                    ORDER BY starttime DESC
-                ) AS rank
-            FROM runepool_history
-            WHERE {}
-        )
-        SELECT
-            MIN(bracket_start) AS starttime,  -- First hour of the bracket
-            MAX(starttime) AS endtime,       -- Last hour of the bracket
-            units,
-            count
-        FROM grouped_data
-        WHERE rank = 1
-        GROUP BY bracket_start, units, count
-        ORDER BY starttime {}
-        LIMIT {}
-        OFFSET {}
-        "#,
-                interval_sql, interval_sql, where_sql, order_sql, effective_limit, offset
-            )
-        }
-    } else {
-        // No interval specified, default behavior
-        format!(
-            r#"
-            SELECT starttime, endtime, units, count
-            FROM runepool_history
-            WHERE {}
-            ORDER BY {} {}
-            LIMIT {} OFFSET {}
-            "#,
-            where_sql, sort_by, order_sql, effective_limit, offset
-        )
-    }
+    let mut query_builder = FilteredQuery::new(
+        r#"
+    WITH hourly AS (
+        SELECT *
+        FROM runepool_history
+        WHERE"#,
+    );
+    add_conditions(&mut query_builder, query);
+    query_builder.push(format!(
+        r#"
+    )
+    SELECT * FROM ({buckets}
+    ) buckets
+    ORDER BY {sort_by} {order_sql}
+"#,
+        buckets = AGGREGATION.select("hourly", interval_unit(query.interval.as_deref())?),
+        sort_by = sort_by,
+        order_sql = order_sql
+    ));
+    query_builder
+        .push(" LIMIT ")
+        .push_bind(effective_limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    Ok(query_builder)
 }
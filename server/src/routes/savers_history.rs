@@ -0,0 +1,122 @@
+use super::aggregation::{interval_unit, Aggregation, Field, Rule};
+use super::utils::{paginate, sort_column, validate_pools, FilteredQuery};
+use crate::error::ApiError;
+use crate::models::savers_history::{SaversHistory, SaversQueryParams};
+use crate::query::Query;
+use actix_web::{web, HttpResponse};
+use std::sync::Arc;
+
+const SORT_COLUMNS: &[&str] = &[
+    "pool",
+    "starttime",
+    "endtime",
+    "saverscount",
+    "saversdepth",
+    "saversunits",
+    "saverearning",
+    "saversapr",
+];
+
+/// Depth, units and count are levels taken from the bucket's last hour; earnings are
+/// summed. `saversdepthrune` adds up to the RUNE value of the depth held over the bucket,
+/// which its earnings are a yield on.
+const AGGREGATION: Aggregation = Aggregation {
+    time_column: "starttime",
+    per_pool: true,
+    hourly: true,
+    fields: &[
+        Field::new("endtime", Rule::Max, "BIGINT"),
+        Field::new("saverscount", Rule::Last, "BIGINT"),
+        Field::new("saversdepth", Rule::Last, "BIGINT"),
+        Field::new("saversunits", Rule::Last, "BIGINT"),
+        Field::new("saverearning", Rule::Sum, "BIGINT"),
+        Field::new("saversdepthrune", Rule::Sum, "FLOAT8"),
+    ],
+};
+
+pub async fn get_savers_history(
+    pool: web::Data<Arc<sqlx::PgPool>>,
+    query: Query<SaversQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    validate_pools(&pool, &query.pool).await?;
+    let mut query_builder = build_query(&query)?;
+    let rows = query_builder
+        .build_query_as::<SaversHistory>()
+        .fetch_all(&***pool)
+        .await?;
+    Ok(HttpResponse::Ok().json(rows))
+}
+
+fn build_query(query: &SaversQueryParams) -> Result<FilteredQuery, ApiError> {
+    // Sorting and pagination
+    let sort_by = sort_column(&query.sort_by, SORT_COLUMNS, "starttime")?;
+    let order = query.order.clone().unwrap_or_else(|| "asc".to_string());
+    let order_sql = if order == "desc" { "DESC" } else { "ASC" };
+    let hard_limit = query.count.unwrap_or(400).min(400);
+    let (pagination_limit, offset) = paginate(query.page, query.limit, query.count);
+    let effective_limit = hard_limit.min(pagination_limit);
+
+    // saverEarning is in RUNE while the depth is in the pool's asset, so the depth is
+    // valued with the hour's assetPrice. The APR scales the bucket's yield per hour of
+    // depth to a year.
+    let mut query_builder = FilteredQuery::new(
+        r#"
+    WITH hourly AS (
+        SELECT
+            s.pool,
+            s.starttime,
+            s.endtime,
+            s.saverscount,
+            s.saversdepth,
+            s.saversunits,
+            en.saverearning,
+            s.saversdepth * d.assetprice::FLOAT8 AS saversdepthrune
+        FROM savers_history s
+        LEFT JOIN earning_history eh
+            ON eh.starttime = s.starttime AND eh.endtime = s.endtime
+        LEFT JOIN earning_history_nested en
+            ON en.earning_history_id = eh.id AND en.pool = s.pool
+        LEFT JOIN depth_price_history d
+            ON d.pool = s.pool AND d.starttime = s.starttime AND d.endtime = s.endtime
+        WHERE"#,
+    );
+
+    // Time range filters
+    query_builder.condition("s.starttime", ">=", query.from);
+    query_builder.condition("s.endtime", "<=", query.to);
+
+    query_builder.in_condition("s.pool", &query.pool);
+    query_builder.condition("s.saversdepth", ">", query.savers_depth_gt);
+    query_builder.condition("s.saversdepth", "<", query.savers_depth_lt);
+    query_builder.condition("s.saverscount", ">", query.savers_count_gt);
+    query_builder.condition("s.saverscount", "<", query.savers_count_lt);
+
+    query_builder.push(format!(
+        r#"
+    )
+    SELECT * FROM (
+        SELECT
+            pool,
+            starttime,
+            endtime,
+            saverscount,
+            saversdepth,
+            saversunits,
+            saverearning,
+            saverearning::FLOAT8 * 8760 / NULLIF(saversdepthrune, 0) AS saversapr
+        FROM ({buckets}
+        ) b
+    ) buckets
+    ORDER BY {sort_by} {order_sql}, pool
+    "#,
+        buckets = AGGREGATION.select("hourly", interval_unit(query.interval.as_deref())?),
+        sort_by = sort_by,
+        order_sql = order_sql,
+    ));
+    query_builder
+        .push(" LIMIT ")
+        .push_bind(effective_limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    Ok(query_builder)
+}
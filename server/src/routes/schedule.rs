@@ -0,0 +1,6 @@
+use actix_web::{web, HttpResponse, Responder};
+use populate::SchedulerState;
+
+pub async fn get_schedule(state: web::Data<SchedulerState>) -> impl Responder {
+    HttpResponse::Ok().json(state.snapshot())
+}
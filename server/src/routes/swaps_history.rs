@@ -1,240 +1,186 @@
-use super::utils::{add_condition, paginate};
+use super::aggregation::{interval_unit, Aggregation, Field, Rule};
+use super::utils::{paginate, sort_column, FilteredQuery};
+use crate::error::ApiError;
 use crate::models::swap_history::{SwapHistory, SwapQueryParams};
-use actix_web::{web, HttpResponse, Responder};
-use sqlx::{Pool, Postgres};
+use crate::query::Query;
+use actix_web::{web, HttpResponse};
 use std::sync::Arc;
 
+const SORT_COLUMNS: &[&str] = &[
+    "starttime",
+    "endtime",
+    "toassetcount",
+    "torunecount",
+    "totradecount",
+    "fromtradecount",
+    "synthmintcount",
+    "synthredeemcount",
+    "totalcount",
+    "toassetvolume",
+    "torunevolume",
+    "totradevolume",
+    "fromtradevolume",
+    "synthmintvolume",
+    "synthredeemvolume",
+    "totalvolume",
+    "toassetvolumeusd",
+    "torunevolumeusd",
+    "totradevolumeusd",
+    "fromtradevolumeusd",
+    "synthmintvolumeusd",
+    "synthredeemvolumeusd",
+    "totalvolumeusd",
+    "toassetfees",
+    "torunefees",
+    "totradefees",
+    "fromtradefees",
+    "synthmintfees",
+    "synthredeemfees",
+    "totalfees",
+    "toassetaverageslip",
+    "toruneaverageslip",
+    "totradeaverageslip",
+    "fromtradeaverageslip",
+    "synthmintaverageslip",
+    "synthredeemaverageslip",
+    "averageslip",
+    "runepriceusd",
+];
+
+/// Counts, volumes and fees add up; each slip is weighted by the volume of its swaps.
+const AGGREGATION: Aggregation = Aggregation {
+    time_column: "starttime",
+    per_pool: false,
+    hourly: true,
+    fields: &[
+        Field::new("endtime", Rule::Max, "BIGINT"),
+        Field::new("toassetcount", Rule::Sum, "BIGINT"),
+        Field::new("torunecount", Rule::Sum, "BIGINT"),
+        Field::new("totradecount", Rule::Sum, "BIGINT"),
+        Field::new("fromtradecount", Rule::Sum, "BIGINT"),
+        Field::new("synthmintcount", Rule::Sum, "BIGINT"),
+        Field::new("synthredeemcount", Rule::Sum, "BIGINT"),
+        Field::new("totalcount", Rule::Sum, "BIGINT"),
+        Field::new("toassetvolume", Rule::Sum, "BIGINT"),
+        Field::new("torunevolume", Rule::Sum, "BIGINT"),
+        Field::new("totradevolume", Rule::Sum, "BIGINT"),
+        Field::new("fromtradevolume", Rule::Sum, "BIGINT"),
+        Field::new("synthmintvolume", Rule::Sum, "BIGINT"),
+        Field::new("synthredeemvolume", Rule::Sum, "BIGINT"),
+        Field::new("totalvolume", Rule::Sum, "BIGINT"),
+        Field::new("toassetvolumeusd", Rule::Sum, "FLOAT8"),
+        Field::new("torunevolumeusd", Rule::Sum, "FLOAT8"),
+        Field::new("totradevolumeusd", Rule::Sum, "FLOAT8"),
+        Field::new("fromtradevolumeusd", Rule::Sum, "FLOAT8"),
+        Field::new("synthmintvolumeusd", Rule::Sum, "FLOAT8"),
+        Field::new("synthredeemvolumeusd", Rule::Sum, "FLOAT8"),
+        Field::new("totalvolumeusd", Rule::Sum, "FLOAT8"),
+        Field::new("toassetfees", Rule::Sum, "BIGINT"),
+        Field::new("torunefees", Rule::Sum, "BIGINT"),
+        Field::new("totradefees", Rule::Sum, "BIGINT"),
+        Field::new("fromtradefees", Rule::Sum, "BIGINT"),
+        Field::new("synthmintfees", Rule::Sum, "BIGINT"),
+        Field::new("synthredeemfees", Rule::Sum, "BIGINT"),
+        Field::new("totalfees", Rule::Sum, "BIGINT"),
+        Field::new(
+            "toassetaverageslip",
+            Rule::WeightedBy("toassetvolume"),
+            "FLOAT8",
+        ),
+        Field::new(
+            "toruneaverageslip",
+            Rule::WeightedBy("torunevolume"),
+            "FLOAT8",
+        ),
+        Field::new(
+            "totradeaverageslip",
+            Rule::WeightedBy("totradevolume"),
+            "FLOAT8",
+        ),
+        Field::new(
+            "fromtradeaverageslip",
+            Rule::WeightedBy("fromtradevolume"),
+            "FLOAT8",
+        ),
+        Field::new(
+            "synthmintaverageslip",
+            Rule::WeightedBy("synthmintvolume"),
+            "FLOAT8",
+        ),
+        Field::new(
+            "synthredeemaverageslip",
+            Rule::WeightedBy("synthredeemvolume"),
+            "FLOAT8",
+        ),
+        Field::new("averageslip", Rule::WeightedBy("totalvolume"), "FLOAT8"),
+        Field::new("runepriceusd", Rule::Last, "FLOAT8"),
+    ],
+};
+
 pub async fn get_swap_history(
     pool: web::Data<Arc<sqlx::PgPool>>,
-    query: web::Query<SwapQueryParams>,
-) -> impl Responder {
-    println!("Getting swap history");
-    let query_str = build_swap_query(&query);
-    println!("Generated query: {}", query_str);
-    let rows = sqlx::query_as::<_, SwapHistory>(&query_str)
+    query: Query<SwapQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    let mut query_builder = build_swap_query(&query)?;
+    let rows = query_builder
+        .build_query_as::<SwapHistory>()
         .fetch_all(&***pool)
-        .await;
-    match rows {
-        Ok(rows) => HttpResponse::Ok().json(rows),
-        Err(err) => HttpResponse::InternalServerError().json({
-            serde_json::json!({"error": "Error fetching data", "details": err.to_string()})
-        }),
-    }
+        .await?;
+    Ok(HttpResponse::Ok().json(rows))
 }
 
-fn build_swap_query(query: &SwapQueryParams) -> String {
-    let mut where_clauses = vec![];
-
+fn add_conditions(query_builder: &mut FilteredQuery, query: &SwapQueryParams) {
     // Time range filters
-    if let Some(from) = query.from {
-        where_clauses.push(format!("starttime >= {}", from));
-    }
-    if let Some(to) = query.to {
-        where_clauses.push(format!("endtime <= {}", to));
-    }
+    query_builder.condition("starttime", ">=", query.from);
+    query_builder.condition("endtime", "<=", query.to);
 
     // Dynamic filters
-    add_condition(
-        &mut where_clauses,
-        "toassetvolume",
-        &query.to_asset_volume_gt,
-        ">",
-    );
-    add_condition(
-        &mut where_clauses,
-        "toassetvolume",
-        &query.to_asset_volume_lt,
-        "<",
-    );
-    add_condition(
-        &mut where_clauses,
-        "toassetvolume",
-        &query.to_asset_volume_eq,
-        "=",
-    );
-
-    add_condition(
-        &mut where_clauses,
-        "totalvolumeusd",
-        &query.total_volume_usd_gt,
-        ">",
-    );
-    add_condition(
-        &mut where_clauses,
-        "totalvolumeusd",
-        &query.total_volume_usd_lt,
-        "<",
-    );
-    add_condition(
-        &mut where_clauses,
-        "totalvolumeusd",
-        &query.total_volume_usd_eq,
-        "=",
-    );
+    query_builder.condition("toassetvolume", ">", query.to_asset_volume_gt);
+    query_builder.condition("toassetvolume", "<", query.to_asset_volume_lt);
+    query_builder.condition("toassetvolume", "=", query.to_asset_volume_eq);
 
-    add_condition(
-        &mut where_clauses,
-        "runepriceusd",
-        &query.rune_price_usd_gt,
-        ">",
-    );
-    add_condition(
-        &mut where_clauses,
-        "runepriceusd",
-        &query.rune_price_usd_lt,
-        "<",
-    );
-    add_condition(
-        &mut where_clauses,
-        "runepriceusd",
-        &query.rune_price_usd_eq,
-        "=",
-    );
+    query_builder.condition("totalvolumeusd", ">", query.total_volume_usd_gt);
+    query_builder.condition("totalvolumeusd", "<", query.total_volume_usd_lt);
+    query_builder.condition("totalvolumeusd", "=", query.total_volume_usd_eq);
 
-    let where_sql = if where_clauses.is_empty() {
-        "TRUE".to_string()
-    } else {
-        where_clauses.join(" AND ")
-    };
+    query_builder.condition("runepriceusd", ">", query.rune_price_usd_gt);
+    query_builder.condition("runepriceusd", "<", query.rune_price_usd_lt);
+    query_builder.condition("runepriceusd", "=", query.rune_price_usd_eq);
+}
 
+fn build_swap_query(query: &SwapQueryParams) -> Result<FilteredQuery, ApiError> {
     // Sorting and pagination
-    let sort_by = query
-        .sort_by
-        .clone()
-        .unwrap_or_else(|| "starttime".to_string());
+    let sort_by = sort_column(&query.sort_by, SORT_COLUMNS, "starttime")?;
     let order = query.order.clone().unwrap_or_else(|| "asc".to_string());
     let order_sql = if order == "desc" { "DESC" } else { "ASC" };
     let hard_limit = query.count.unwrap_or(400).min(400);
     let (pagination_limit, offset) = paginate(query.page, query.limit, query.count);
     let effective_limit = hard_limit.min(pagination_limit);
 
-    if let Some(interval) = &query.interval {
-        let interval_seconds = match interval.as_str() {
-            "hour" => 3600,
-            "day" => 86400,
-            "week" => 604800,
-            "month" => 2592000,
-            "year" => 31536000,
-            _ => 3600,
-        };
-
-        format!(
-            r#"
-            WITH grouped_data AS (
-                SELECT
-                    (starttime / {interval_seconds}) * {interval_seconds} AS bracket_start,
-                    id,
-                    starttime,
-                    endtime,
-                    toassetcount,
-                    torunecount,
-                    totradecount,
-                    fromtradecount,
-                    synthmintcount,
-                    synthredeemcount,
-                    totalcount,
-                    toassetvolume,
-                    torunevolume,
-                    totradevolume,
-                    fromtradevolume,
-                    synthmintvolume,
-                    synthredeemvolume,
-                    totalvolume,
-                    toassetvolumeusd::FLOAT8 AS toassetvolumeusd,
-                    torunevolumeusd::FLOAT8 AS torunevolumeusd,
-                    totradevolumeusd::FLOAT8 AS totradevolumeusd,
-                    fromtradevolumeusd::FLOAT8 AS fromtradevolumeusd,
-                    synthmintvolumeusd::FLOAT8 AS synthmintvolumeusd,
-                    synthredeemvolumeusd::FLOAT8 AS synthredeemvolumeusd,
-                    totalvolumeusd::FLOAT8 AS totalvolumeusd,
-                    toassetfees,
-                    torunefees,
-                    totradefees,
-                    fromtradefees,
-                    synthmintfees,
-                    synthredeemfees,
-                    totalfees,
-                    toassetaverageslip::FLOAT8 AS toassetaverageslip,
-                    toruneaverageslip::FLOAT8 AS toruneaverageslip,
-                    totradeaverageslip::FLOAT8 AS totradeaverageslip,
-                    fromtradeaverageslip::FLOAT8 AS fromtradeaverageslip,
-                    synthmintaverageslip::FLOAT8 AS synthmintaverageslip,
-                    synthredeemaverageslip::FLOAT8 AS synthredeemaverageslip,
-                    averageslip::FLOAT8 AS averageslip,
-                    runepriceusd::FLOAT8 AS runepriceusd,
-                    ROW_NUMBER() OVER (
-                        PARTITION BY (starttime / {interval_seconds})
-                        ORDER BY {sort_by} DESC
-                    ) AS rank
-                FROM swap_history
-                WHERE {where_sql}
-            )
-            SELECT *
-            FROM grouped_data
-            WHERE rank = 1
-            ORDER BY {sort_by} {order_sql}
-            LIMIT {limit} OFFSET {offset}
-            "#,
-            interval_seconds = interval_seconds,
-            where_sql = where_sql,
-            sort_by = sort_by,
-            order_sql = order_sql,
-            limit = effective_limit,
-            offset = offset
-        )
-    } else {
-        format!(
-            r#"
-            SELECT
-                id,
-                starttime,
-                endtime,
-                toassetcount,
-                torunecount,
-                totradecount,
-                fromtradecount,
-                synthmintcount,
-                synthredeemcount,
-                totalcount,
-                toassetvolume,
-                torunevolume,
-                totradevolume,
-                fromtradevolume,
-                synthmintvolume,
-                synthredeemvolume,
-                totalvolume,
-                toassetvolumeusd::FLOAT8 AS toassetvolumeusd,
-                torunevolumeusd::FLOAT8 AS torunevolumeusd,
-                totradevolumeusd::FLOAT8 AS totradevolumeusd,
-                fromtradevolumeusd::FLOAT8 AS fromtradevolumeusd,
-                synthmintvolumeusd::FLOAT8 AS synthmintvolumeusd,
-                synthredeemvolumeusd::FLOAT8 AS synthredeemvolumeusd,
-                totalvolumeusd::FLOAT8 AS totalvolumeusd,
-                toassetfees,
-                torunefees,
-                totradefees,
-                fromtradefees,
-                synthmintfees,
-                synthredeemfees,
-                totalfees,
-                toassetaverageslip::FLOAT8 AS toassetaverageslip,
-                toruneaverageslip::FLOAT8 AS toruneaverageslip,
-                totradeaverageslip::FLOAT8 AS totradeaverageslip,
-                fromtradeaverageslip::FLOAT8 AS fromtradeaverageslip,
-                synthmintaverageslip::FLOAT8 AS synthmintaverageslip,
-                synthredeemaverageslip::FLOAT8 AS synthredeemaverageslip,
-                averageslip::FLOAT8 AS averageslip,
-                runepriceusd::FLOAT8 AS runepriceusd
-            FROM swap_history
-            WHERE {where_sql}
-            ORDER BY {sort_by} {order_sql}
-            LIMIT {limit} OFFSET {offset}
-            "#,
-            where_sql = where_sql,
-            sort_by = sort_by,
-            order_sql = order_sql,
-            limit = effective_limit,
-            offset = offset
-        )
-    }
+    let mut query_builder = FilteredQuery::new(
+        r#"
+    WITH hourly AS (
+        SELECT *
+        FROM swap_history
+        WHERE"#,
+    );
+    // Filters apply to the hourly rows, before they are aggregated
+    add_conditions(&mut query_builder, query);
+    query_builder.push(format!(
+        r#"
+    )
+    SELECT * FROM ({buckets}
+    ) buckets
+    ORDER BY {sort_by} {order_sql}
+    "#,
+        buckets = AGGREGATION.select("hourly", interval_unit(query.interval.as_deref())?),
+        sort_by = sort_by,
+        order_sql = order_sql,
+    ));
+    query_builder
+        .push(" LIMIT ")
+        .push_bind(effective_limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    Ok(query_builder)
 }
@@ -0,0 +1,106 @@
+use super::aggregation::{interval_unit, Aggregation, Field, Rule};
+use super::utils::{paginate, sort_column, FilteredQuery};
+use crate::error::ApiError;
+use crate::models::tvl_history::{TvlHistory, TvlQueryParams};
+use crate::query::Query;
+use actix_web::{web, HttpResponse};
+use std::sync::Arc;
+
+const SORT_COLUMNS: &[&str] = &[
+    "starttime",
+    "endtime",
+    "totalvaluepooled",
+    "totalvaluebonded",
+    "totalvaluelocked",
+    "runepriceusd",
+];
+
+/// TVL is a level, not a flow: a bucket reports its last hour.
+const AGGREGATION: Aggregation = Aggregation {
+    time_column: "starttime",
+    per_pool: false,
+    hourly: true,
+    fields: &[
+        Field::new("endtime", Rule::Max, "BIGINT"),
+        Field::new("totalvaluepooled", Rule::Last, "BIGINT"),
+        Field::new("totalvaluebonded", Rule::Last, "BIGINT"),
+        Field::new("totalvaluelocked", Rule::Last, "BIGINT"),
+        Field::new("runepriceusd", Rule::Last, "FLOAT8"),
+        Field::new("pools", Rule::Last, "JSONB"),
+    ],
+};
+
+pub async fn get_tvl_history(
+    pool: web::Data<Arc<sqlx::PgPool>>,
+    query: Query<TvlQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    let mut query_builder = build_query(&query)?;
+    let rows = query_builder
+        .build_query_as::<TvlHistory>()
+        .fetch_all(&***pool)
+        .await?;
+    Ok(HttpResponse::Ok().json(rows))
+}
+
+fn build_query(query: &TvlQueryParams) -> Result<FilteredQuery, ApiError> {
+    // Sorting and pagination
+    let sort_by = sort_column(&query.sort_by, SORT_COLUMNS, "starttime")?;
+    let order = query.order.clone().unwrap_or_else(|| "asc".to_string());
+    let order_sql = if order == "desc" { "DESC" } else { "ASC" };
+    let hard_limit = query.count.unwrap_or(400).min(400);
+    let (pagination_limit, offset) = paginate(query.page, query.limit, query.count);
+    let effective_limit = hard_limit.min(pagination_limit);
+
+    let mut query_builder = FilteredQuery::new(
+        r#"
+    WITH hourly AS (
+        SELECT
+            th.starttime,
+            th.endtime,
+            th.totalvaluepooled,
+            th.totalvaluebonded,
+            th.totalvaluelocked,
+            th.runepriceusd,
+            COALESCE((
+                SELECT jsonb_agg(
+                    jsonb_build_object('pool', tp.pool, 'total_depth', tp.totaldepth)
+                    ORDER BY tp.pool
+                )
+                FROM tvl_history_pools tp
+                WHERE tp.tvl_history_id = th.id
+            ), '[]'::jsonb) AS pools
+        FROM tvl_history th
+        WHERE"#,
+    );
+
+    // Time range filters
+    query_builder.condition("starttime", ">=", query.from);
+    query_builder.condition("endtime", "<=", query.to);
+
+    query_builder.condition("totalvaluepooled", ">", query.total_value_pooled_gt);
+    query_builder.condition("totalvaluepooled", "<", query.total_value_pooled_lt);
+    query_builder.condition("totalvaluebonded", ">", query.total_value_bonded_gt);
+    query_builder.condition("totalvaluebonded", "<", query.total_value_bonded_lt);
+    query_builder.condition("totalvaluelocked", ">", query.total_value_locked_gt);
+    query_builder.condition("totalvaluelocked", "<", query.total_value_locked_lt);
+    query_builder.condition("runepriceusd", ">", query.rune_price_usd_gt);
+    query_builder.condition("runepriceusd", "<", query.rune_price_usd_lt);
+
+    query_builder.push(format!(
+        r#"
+    )
+    SELECT * FROM ({buckets}
+    ) buckets
+    ORDER BY {sort_by} {order_sql}
+    "#,
+        buckets = AGGREGATION.select("hourly", interval_unit(query.interval.as_deref())?),
+        sort_by = sort_by,
+        order_sql = order_sql,
+    ));
+    query_builder
+        .push(" LIMIT ")
+        .push_bind(effective_limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    Ok(query_builder)
+}
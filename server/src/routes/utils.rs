@@ -1,24 +1,158 @@
+use crate::error::ApiError;
+use sqlx::postgres::{PgArguments, PgRow};
+use sqlx::query::QueryAs;
+use sqlx::{FromRow, Postgres, QueryBuilder};
 
-pub fn add_condition<T: std::fmt::Display>(
-    where_clauses: &mut Vec<String>,
-    column: &str,
-    value: &Option<T>,
-    operator: &str,
-) {
-    if let Some(val) = value {
-        where_clauses.push(format!("{} {} {}", column, operator, val));
+/// A query whose WHERE clause is assembled from request filters. Filter values are always
+/// bound as parameters: the SQL text only holds the column names and operators written in
+/// the routes, and `sort_by` columns checked by `sort_column`.
+pub struct FilteredQuery {
+    builder: QueryBuilder<'static, Postgres>,
+    conditions: usize,
+    in_where: bool,
+}
+
+impl FilteredQuery {
+    /// Starts the query with `sql`, which ends with the `WHERE` the conditions follow.
+    pub fn new(sql: impl Into<String>) -> Self {
+        FilteredQuery {
+            builder: QueryBuilder::new(sql),
+            conditions: 0,
+            in_where: true,
+        }
+    }
+
+    fn push_condition(&mut self, sql: &str) -> &mut QueryBuilder<'static, Postgres> {
+        self.builder
+            .push(if self.conditions == 0 { " " } else { " AND " });
+        self.conditions += 1;
+        self.builder.push(sql)
+    }
+
+    /// Adds `column operator value` when the filter is set.
+    pub fn condition<T>(&mut self, column: &'static str, operator: &'static str, value: Option<T>)
+    where
+        T: 'static + sqlx::Encode<'static, Postgres> + sqlx::Type<Postgres>,
+    {
+        if let Some(value) = value {
+            self.push_condition(&format!("{} {} ", column, operator))
+                .push_bind(value);
+        }
+    }
+
+    /// Adds `column = ANY(values)` for a comma-separated list of values.
+    pub fn in_condition(&mut self, column: &'static str, values: &Option<String>) {
+        let Some(values) = values else {
+            return;
+        };
+        let values: Vec<String> = values
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+            .collect();
+        if !values.is_empty() {
+            self.push_condition(&format!("{} = ANY(", column))
+                .push_bind(values)
+                .push(")");
+        }
+    }
+
+    /// Adds a condition that takes no request values.
+    pub fn raw_condition(&mut self, sql: &'static str) {
+        self.push_condition(sql);
+    }
+
+    /// Appends SQL after the WHERE clause. Request values go through `push_bind`.
+    pub fn push(&mut self, sql: impl std::fmt::Display) -> &mut Self {
+        if self.in_where {
+            if self.conditions == 0 {
+                self.builder.push(" TRUE");
+            }
+            self.in_where = false;
+        }
+        self.builder.push(sql);
+        self
+    }
+
+    pub fn push_bind<T>(&mut self, value: T) -> &mut Self
+    where
+        T: 'static + sqlx::Encode<'static, Postgres> + sqlx::Type<Postgres>,
+    {
+        self.builder.push_bind(value);
+        self
+    }
+
+    pub fn sql(&self) -> &str {
+        self.builder.sql()
+    }
+
+    pub fn build_query_as<'q, T: FromRow<'q, PgRow>>(
+        &'q mut self,
+    ) -> QueryAs<'q, Postgres, T, PgArguments> {
+        self.builder.build_query_as()
+    }
+}
+
+/// The `sort_by` column if it is one of `columns`, `default` when it is not given, and a
+/// validation error otherwise.
+pub fn sort_column(
+    sort_by: &Option<String>,
+    columns: &[&'static str],
+    default: &'static str,
+) -> Result<&'static str, ApiError> {
+    let Some(sort_by) = sort_by else {
+        return Ok(default);
+    };
+    match columns.iter().find(|column| **column == sort_by.as_str()) {
+        Some(column) => Ok(column),
+        None => Err(ApiError::validation(
+            "invalid_sort_by",
+            format!("`{}` is not one of: {}", sort_by, columns.join(", ")),
+        )),
+    }
+}
+
+/// Rejects a comma-separated `pool` parameter naming pools Midgard has never listed with a
+/// validation error. Every pool is accepted until the first `/v2/pools` snapshot is stored.
+pub async fn validate_pools(db: &sqlx::PgPool, pools: &Option<String>) -> Result<(), ApiError> {
+    let Some(pools) = pools else {
+        return Ok(());
+    };
+    let names: Vec<&str> = pools
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .collect();
+    let unknown: Vec<String> = sqlx::query_scalar(
+        "
+    SELECT name FROM UNNEST($1::TEXT[]) name
+    WHERE EXISTS (SELECT 1 FROM pools)
+        AND NOT EXISTS (SELECT 1 FROM pools p WHERE p.asset = name)",
+    )
+    .bind(&names)
+    .fetch_all(db)
+    .await?;
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::validation(
+            "unknown_pool",
+            format!("Unknown pool: {}", unknown.join(", ")),
+        ))
     }
 }
+
 pub fn paginate(page: Option<i32>, limit: Option<i32>, count: Option<i32>) -> (i32, i32) {
     let default_per_page = 10;
     let max_total_count = 4000;
-    let max_per_page = 100; 
+    let max_per_page = 100;
     let per_page_limit = limit
         .unwrap_or(default_per_page)
         .max(1) // At least 1
-        .min(max_per_page); 
+        .min(max_per_page);
     let total_count_limit = count
-        .unwrap_or(max_total_count) 
+        .unwrap_or(max_total_count)
         .max(1) // At least 1
         .min(max_total_count);
 
@@ -2,7 +2,7 @@ use dotenvy;
 use lazy_static::lazy_static;
 use sqlx::{postgres::PgPool, Error};
 use std::{env, sync::Arc};
-use tokio::sync::{Mutex, OnceCell};
+use tokio::sync::OnceCell;
 lazy_static! {
     static ref DB_POOL: OnceCell<Arc<PgPool>> = OnceCell::new();
 }